@@ -0,0 +1,168 @@
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashSet;
+use tracing::{info, instrument};
+use utoipa::ToSchema;
+
+use crate::{
+    account::backup::{AccountBackup, BACKUP_VERSION},
+    storage::Repositories,
+    word::review_log_repository::ReviewLogFilter,
+};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, ToSchema)]
+pub struct RestoreReport {
+    pub sets: usize,
+    pub released_words: usize,
+    pub rules: usize,
+    /// Review log entries appended; entries already present are skipped.
+    pub reviews: usize,
+}
+
+pub struct AccountService {
+    repositories: Repositories,
+}
+
+impl AccountService {
+    pub fn new(repositories: Repositories) -> Self {
+        Self { repositories }
+    }
+
+    #[instrument(skip(self), fields(user_login = %user_login))]
+    pub async fn export(&self, user_login: &str, with_password: bool) -> Result<AccountBackup> {
+        info!("Exporting account {}", user_login);
+        let user = self
+            .repositories
+            .users
+            .get_user(user_login)
+            .await?
+            .ok_or_else(|| anyhow!("User {} not found", user_login))?;
+
+        let backup = AccountBackup {
+            version: BACKUP_VERSION,
+            login: user_login.to_owned(),
+            exported_at: Utc::now(),
+            password_hash: with_password.then_some(user.password_hash),
+            sets: self.repositories.sets.list_all(user_login).await?,
+            released_words: self
+                .repositories
+                .releases
+                .list_all_words(user_login)
+                .await?,
+            rules: self.repositories.rules.list_all(user_login).await?,
+            review_log: self
+                .repositories
+                .review_logs
+                .list(user_login, &ReviewLogFilter::default())
+                .await?,
+            settings: Some(self.repositories.settings.load(user_login).await?),
+        };
+
+        info!(
+            "Exported account {}: {} sets, {} released words, {} rules, {} reviews",
+            user_login,
+            backup.sets.len(),
+            backup.released_words.len(),
+            backup.rules.len(),
+            backup.review_log.len()
+        );
+        Ok(backup)
+    }
+
+    /// Imports a backup into `target_login`, or into the backup's own login
+    /// when none is given. Records with the same id are overwritten, so
+    /// restoring the same backup twice is harmless.
+    ///
+    /// With `create_user`, a missing login is created from the backup's
+    /// password hash. Hashes are salted with the login, so this only works
+    /// when restoring under the original login.
+    #[instrument(skip(self, backup), fields(source_login = %backup.login))]
+    pub async fn restore(
+        &self,
+        backup: AccountBackup,
+        target_login: Option<&str>,
+        create_user: bool,
+    ) -> Result<RestoreReport> {
+        backup.validate()?;
+        let user_login = target_login.unwrap_or(&backup.login).to_owned();
+        info!("Restoring account {} into {}", backup.login, user_login);
+
+        if self
+            .repositories
+            .users
+            .get_user(&user_login)
+            .await?
+            .is_none()
+        {
+            match (create_user, &backup.password_hash) {
+                (true, Some(password_hash)) if user_login == backup.login => {
+                    info!("Creating user {} from backup", user_login);
+                    self.repositories
+                        .users
+                        .save_user(&user_login, password_hash)
+                        .await?;
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "User {} not found, register it before restoring",
+                        user_login
+                    ));
+                }
+            }
+        }
+
+        if let Some(settings) = &backup.settings {
+            self.repositories
+                .settings
+                .save(&user_login, settings)
+                .await?;
+        }
+
+        let mut report = RestoreReport::default();
+        for set in &backup.sets {
+            self.repositories.sets.save(&user_login, set).await?;
+            report.sets += 1;
+        }
+
+        if !backup.released_words.is_empty() {
+            self.repositories
+                .releases
+                .save(&user_login, &backup.released_words)
+                .await?;
+            report.released_words = backup.released_words.len();
+        }
+
+        for rule in &backup.rules {
+            self.repositories.rules.save(&user_login, rule).await?;
+            report.rules += 1;
+        }
+
+        let existing = self
+            .repositories
+            .review_logs
+            .list(&user_login, &ReviewLogFilter::default())
+            .await?
+            .into_iter()
+            .map(|x| x.id().to_owned())
+            .collect::<HashSet<_>>();
+        let reviews = backup
+            .review_log
+            .into_iter()
+            .filter(|x| !existing.contains(x.id()))
+            .collect::<Vec<_>>();
+        if !reviews.is_empty() {
+            self.repositories
+                .review_logs
+                .append(&user_login, &reviews)
+                .await?;
+            report.reviews = reviews.len();
+        }
+
+        info!(
+            "Restored account {}: {} sets, {} released words, {} rules, {} reviews",
+            user_login, report.sets, report.released_words, report.rules, report.reviews
+        );
+        Ok(report)
+    }
+}
@@ -0,0 +1,92 @@
+use axum::{
+    Extension, Json,
+    extract::{DefaultBodyLimit, State},
+    http::StatusCode,
+    middleware,
+};
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    account::{
+        account_service::{AccountService, RestoreReport},
+        backup::AccountBackup,
+    },
+    environment::auth::{Claims, JwtConfig, auth_middleware},
+};
+
+/// Backups carry the whole review history, so they outgrow axum's default
+/// 2 MB body limit quickly.
+const BACKUP_BODY_LIMIT: usize = 256 * 1024 * 1024;
+
+#[derive(Clone)]
+struct ApiState {
+    account_service: Arc<AccountService>,
+}
+
+pub fn account_api_router(account_service: AccountService, jwt_config: JwtConfig) -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(export_account))
+        .routes(routes!(restore_account))
+        .layer(DefaultBodyLimit::max(BACKUP_BODY_LIMIT))
+        .layer(middleware::from_fn_with_state(
+            jwt_config.clone(),
+            auth_middleware,
+        ))
+        .with_state(ApiState {
+            account_service: Arc::new(account_service),
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/export",
+    responses(
+        (status = 200, description = "Versioned backup of the whole account", content_type = "application/json"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn export_account(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<AccountBackup>, (StatusCode, String)> {
+    info!("Exporting account {}", claims.sub);
+    match state.account_service.export(&claims.sub, false).await {
+        Ok(backup) => Ok(Json(backup)),
+        Err(e) => {
+            error!("Failed to export account {}: {}", claims.sub, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/restore",
+    request_body(description = "Backup produced by the export endpoint, possibly of another login", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Backup restored into the current account", body = RestoreReport),
+        (status = 400, description = "Invalid backup")
+    )
+)]
+#[instrument(skip(state, claims, backup))]
+async fn restore_account(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Json(backup): Json<AccountBackup>,
+) -> Result<Json<RestoreReport>, (StatusCode, String)> {
+    info!("Restoring backup of {} into {}", backup.login, claims.sub);
+    match state
+        .account_service
+        .restore(backup, Some(&claims.sub), false)
+        .await
+    {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            error!("Failed to restore account {}: {}", claims.sub, e);
+            Err((StatusCode::BAD_REQUEST, e.to_string()))
+        }
+    }
+}
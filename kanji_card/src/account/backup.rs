@@ -0,0 +1,85 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use ulid::Ulid;
+
+use crate::{
+    rule::rule::GrammarRule,
+    word::domain::{WordCard, review::ReviewLogEntry, set::LearnSet, settings::LearningSettings},
+};
+
+/// Version written by this build. Restores accept this and older versions.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Everything stored for one login, as a single document.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountBackup {
+    pub version: u32,
+    pub login: String,
+    pub exported_at: DateTime<Utc>,
+
+    /// Only used when restoring from the command line into a login that
+    /// does not exist yet.
+    #[serde(default)]
+    pub password_hash: Option<String>,
+
+    #[serde(default)]
+    pub sets: Vec<LearnSet>,
+    #[serde(default)]
+    pub released_words: Vec<WordCard>,
+    #[serde(default)]
+    pub rules: Vec<GrammarRule>,
+    #[serde(default)]
+    pub review_log: Vec<ReviewLogEntry>,
+    /// Absent in older backups; the target keeps its own settings then.
+    #[serde(default)]
+    pub settings: Option<LearningSettings>,
+}
+
+impl AccountBackup {
+    pub fn validate(&self) -> Result<()> {
+        if self.version == 0 || self.version > BACKUP_VERSION {
+            return Err(anyhow!(
+                "Unsupported backup version {}, expected at most {}",
+                self.version,
+                BACKUP_VERSION
+            ));
+        }
+
+        if self.login.trim().is_empty() {
+            return Err(anyhow!("Backup has no login"));
+        }
+
+        check_ids("set", self.sets.iter().map(|x| x.id()))?;
+        check_ids(
+            "word",
+            self.sets
+                .iter()
+                .flat_map(|x| x.words())
+                .chain(self.released_words.iter())
+                .map(|x| x.id()),
+        )?;
+        check_ids("rule", self.rules.iter().map(|x| x.id()))?;
+        check_ids("review", self.review_log.iter().map(|x| x.id()))?;
+        if let Some(settings) = &self.settings {
+            settings.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Ids must be unique ULIDs; the file backend builds paths from them, so
+/// anything else could write outside the user's directory.
+fn check_ids<'a>(kind: &str, ids: impl Iterator<Item = &'a str>) -> Result<()> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if Ulid::from_string(id).is_err() {
+            return Err(anyhow!("Invalid {} id {:?} in backup", kind, id));
+        }
+        if !seen.insert(id) {
+            return Err(anyhow!("Duplicate {} id {} in backup", kind, id));
+        }
+    }
+    Ok(())
+}
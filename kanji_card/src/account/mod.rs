@@ -0,0 +1,3 @@
+pub mod account_service;
+pub mod api;
+pub mod backup;
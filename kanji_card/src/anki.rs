@@ -0,0 +1,376 @@
+//! Reading and writing of Anki `.apkg` packages (collection schema 11).
+
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use rusqlite::{Connection, params};
+use serde_json::{Value, json};
+use sha1::{Digest, Sha1};
+use std::io::{Cursor, Read, Write};
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+/// Fixed ids so that repeated exports update the same note type and deck
+/// in Anki instead of creating copies.
+const MODEL_ID: i64 = 1_718_000_000_001;
+const DECK_ID: i64 = 1_718_000_000_002;
+
+const MODEL_NAME: &str = "Kanji Card";
+const DECK_NAME: &str = "Kanji Card";
+const FIELDS: [&str; 4] = ["Word", "Reading", "Translation", "State"];
+const TAG: &str = "kanji_card";
+
+const FIELD_SEPARATOR: char = '\x1f';
+
+const CARD_CSS: &str = ".card { font-family: sans-serif; font-size: 24px; text-align: center; }
+.word { font-size: 48px; }
+.reading { color: #666; }";
+
+#[derive(Debug, Clone)]
+pub struct AnkiNote {
+    /// Stable note identity, our card id on export.
+    pub guid: String,
+    pub word: String,
+    pub reading: Option<String>,
+    pub translation: String,
+    pub state: Option<String>,
+}
+
+/// Builds an `.apkg` with one card per note.
+pub fn write_package(notes: &[AnkiNote]) -> Result<Vec<u8>> {
+    let file = tempfile::NamedTempFile::new()?;
+    {
+        let conn = Connection::open(file.path())?;
+        create_collection(&conn)?;
+        insert_notes(&conn, notes)?;
+    }
+    let collection = std::fs::read(file.path())?;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("collection.anki2", SimpleFileOptions::default())?;
+    zip.write_all(&collection)?;
+    zip.start_file("media", SimpleFileOptions::default())?;
+    zip.write_all(b"{}")?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Note of any type read back from a package, with its fields by name.
+#[derive(Debug, Clone)]
+pub struct AnkiRecord {
+    pub fields: Vec<(String, String)>,
+}
+
+/// Reads every note from an `.apkg`, whatever its note type. Field values
+/// are stripped of HTML markup.
+pub fn read_package(bytes: &[u8]) -> Result<Vec<AnkiRecord>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    // Anki 2.1.50+ stores the real collection zstd-compressed next to a
+    // legacy stub that only holds an "update Anki" note.
+    if archive.index_for_name("collection.anki21").is_none()
+        && archive.index_for_name("collection.anki21b").is_some()
+    {
+        return Err(anyhow!(
+            "Package uses the collection.anki21b format of Anki 2.1.50+, \
+             export it again with \"Support older Anki versions\" enabled"
+        ));
+    }
+    let name = ["collection.anki21", "collection.anki2"]
+        .into_iter()
+        .find(|x| archive.index_for_name(x).is_some())
+        .ok_or_else(|| anyhow!("Package has no collection"))?;
+
+    let mut collection = vec![];
+    archive.by_name(name)?.read_to_end(&mut collection)?;
+
+    let file = tempfile::NamedTempFile::new()?;
+    std::fs::write(file.path(), &collection)?;
+    let conn = Connection::open(file.path())?;
+
+    let models: Value =
+        serde_json::from_str(
+            &conn.query_row("SELECT models FROM col", [], |row| row.get::<_, String>(0))?,
+        )?;
+
+    let mut statement = conn.prepare("SELECT mid, flds FROM notes ORDER BY id")?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(model_id, fields)| {
+            let names = field_names(&models, model_id);
+            let fields = fields
+                .split(FIELD_SEPARATOR)
+                .enumerate()
+                .map(|(position, value)| {
+                    let name = names
+                        .get(position)
+                        .cloned()
+                        .unwrap_or_else(|| (position + 1).to_string());
+                    (name, strip_html(value))
+                })
+                .collect();
+            AnkiRecord { fields }
+        })
+        .collect())
+}
+
+fn field_names(models: &Value, model_id: i64) -> Vec<String> {
+    models[model_id.to_string()]["flds"]
+        .as_array()
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|x| x["name"].as_str().map(str::to_owned))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn create_collection(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE col (
+            id integer primary key, crt integer not null, mod integer not null,
+            scm integer not null, ver integer not null, dty integer not null,
+            usn integer not null, ls integer not null, conf text not null,
+            models text not null, decks text not null, dconf text not null,
+            tags text not null
+        );
+        CREATE TABLE notes (
+            id integer primary key, guid text not null, mid integer not null,
+            mod integer not null, usn integer not null, tags text not null,
+            flds text not null, sfld integer not null, csum integer not null,
+            flags integer not null, data text not null
+        );
+        CREATE TABLE cards (
+            id integer primary key, nid integer not null, did integer not null,
+            ord integer not null, mod integer not null, usn integer not null,
+            type integer not null, queue integer not null, due integer not null,
+            ivl integer not null, factor integer not null, reps integer not null,
+            lapses integer not null, left integer not null, odue integer not null,
+            odid integer not null, flags integer not null, data text not null
+        );
+        CREATE TABLE revlog (
+            id integer primary key, cid integer not null, usn integer not null,
+            ease integer not null, ivl integer not null, lastIvl integer not null,
+            factor integer not null, time integer not null, type integer not null
+        );
+        CREATE TABLE graves (
+            usn integer not null, oid integer not null, type integer not null
+        );
+        CREATE INDEX ix_notes_usn ON notes (usn);
+        CREATE INDEX ix_cards_usn ON cards (usn);
+        CREATE INDEX ix_revlog_usn ON revlog (usn);
+        CREATE INDEX ix_cards_nid ON cards (nid);
+        CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+        CREATE INDEX ix_revlog_cid ON revlog (cid);
+        CREATE INDEX ix_notes_csum ON notes (csum);",
+    )?;
+
+    let now = Utc::now();
+    let seconds = now.timestamp();
+    let millis = now.timestamp_millis();
+
+    let fields = FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| {
+            json!({
+                "name": name, "ord": ord, "sticky": false, "rtl": false,
+                "font": "Arial", "size": 20, "media": []
+            })
+        })
+        .collect::<Vec<_>>();
+    let models = json!({
+        MODEL_ID.to_string(): {
+            "id": MODEL_ID, "name": MODEL_NAME, "type": 0, "mod": seconds, "usn": -1,
+            "sortf": 0, "did": DECK_ID, "flds": fields,
+            "tmpls": [{
+                "name": "Recognition", "ord": 0, "did": null, "bqfmt": "", "bafmt": "",
+                "qfmt": "<div class=\"word\">{{Word}}</div>",
+                "afmt": "{{FrontSide}}<hr id=answer><div class=\"reading\">{{Reading}}</div><div>{{Translation}}</div>"
+            }],
+            "css": CARD_CSS, "latexPre": "", "latexPost": "", "tags": [], "vers": [],
+            "req": [[0, "any", [0]]]
+        }
+    });
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id, "name": name, "mod": seconds, "usn": -1, "desc": "", "dyn": 0,
+            "conf": 1, "collapsed": false, "extendNew": 10, "extendRev": 50,
+            "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0]
+        })
+    };
+    let decks = json!({
+        "1": deck(1, "Default"),
+        DECK_ID.to_string(): deck(DECK_ID, DECK_NAME),
+    });
+    let dconf = json!({
+        "1": {
+            "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60,
+            "autoplay": true, "timer": 0, "replayq": true, "dyn": false,
+            "new": {
+                "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500,
+                "order": 1, "perDay": 20, "bury": true, "separate": true
+            },
+            "rev": {
+                "perDay": 200, "ease4": 1.3, "fuzz": 0.05, "ivlFct": 1,
+                "maxIvl": 36500, "bury": true, "minSpace": 1
+            },
+            "lapse": {
+                "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0
+            }
+        }
+    });
+    let conf = json!({
+        "nextPos": 1, "estTimes": true, "activeDecks": [1], "sortType": "noteFld",
+        "timeLim": 0, "sortBackwards": false, "addToCur": true, "curDeck": 1,
+        "newSpread": 0, "dueCounts": true, "curModel": MODEL_ID.to_string(),
+        "collapseTime": 1200
+    });
+
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![
+            seconds,
+            millis,
+            conf.to_string(),
+            models.to_string(),
+            decks.to_string(),
+            dconf.to_string()
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_notes(conn: &Connection, notes: &[AnkiNote]) -> Result<()> {
+    let now = Utc::now();
+    let seconds = now.timestamp();
+    let base_id = now.timestamp_millis();
+
+    for (position, note) in notes.iter().enumerate() {
+        let id = base_id + position as i64;
+        let fields = [
+            note.word.as_str(),
+            note.reading.as_deref().unwrap_or_default(),
+            note.translation.as_str(),
+            note.state.as_deref().unwrap_or_default(),
+        ]
+        .map(escape_html)
+        .join(&FIELD_SEPARATOR.to_string());
+        let tags = match &note.state {
+            Some(state) => format!(" {TAG} {state} "),
+            None => format!(" {TAG} "),
+        };
+
+        conn.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                id,
+                note.guid,
+                MODEL_ID,
+                seconds,
+                tags,
+                fields,
+                note.word,
+                checksum(&note.word)
+            ],
+        )?;
+        conn.execute(
+            "INSERT INTO cards VALUES (?1, ?1, ?2, 0, ?3, -1, 0, 0, ?4, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![id, DECK_ID, seconds, position as i64 + 1],
+        )?;
+    }
+    Ok(())
+}
+
+/// First 32 bits of the SHA-1 of the sort field, as Anki computes it.
+fn checksum(field: &str) -> i64 {
+    let digest = Sha1::digest(field.as_bytes());
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as i64
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Drops tags and decodes the entities Anki's editor produces.
+fn strip_html(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut in_tag = false;
+    for ch in value.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+        .trim()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(guid: &str, word: &str, reading: Option<&str>, translation: &str) -> AnkiNote {
+        AnkiNote {
+            guid: guid.to_owned(),
+            word: word.to_owned(),
+            reading: reading.map(str::to_owned),
+            translation: translation.to_owned(),
+            state: Some("Tobe".to_owned()),
+        }
+    }
+
+    #[test]
+    fn exported_package_reads_back() {
+        let notes = [
+            note("a", "猫", Some("ねこ"), "кошка"),
+            note("b", "犬", None, "собака <dog> & co"),
+        ];
+
+        let records = read_package(&write_package(&notes).unwrap()).unwrap();
+
+        let fields = records.iter().map(|x| x.fields.clone()).collect::<Vec<_>>();
+        let field = |name: &str, value: &str| (name.to_owned(), value.to_owned());
+        assert_eq!(
+            fields,
+            [
+                vec![
+                    field("Word", "猫"),
+                    field("Reading", "ねこ"),
+                    field("Translation", "кошка"),
+                    field("State", "Tobe"),
+                ],
+                vec![
+                    field("Word", "犬"),
+                    field("Reading", ""),
+                    field("Translation", "собака <dog> & co"),
+                    field("State", "Tobe"),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_markup_from_fields() {
+        assert_eq!(strip_html("<b>猫</b>&nbsp;<br>ねこ"), "猫 ねこ");
+        assert_eq!(
+            strip_html("a &lt;b&gt; &amp; &quot;c&quot;"),
+            "a <b> & \"c\""
+        );
+    }
+}
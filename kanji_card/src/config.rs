@@ -1,5 +1,5 @@
 use serde::Deserialize;
-use std::time::Duration;
+use std::{path::Path, time::Duration};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerConfig {
@@ -15,6 +15,62 @@ pub struct OpenRouterConfig {
     pub image_model: String,
     pub reasoning_model: String,
     pub max_completion_tokens: u32,
+    /// Sends a JSON schema as `response_format`; turn off for models that
+    /// reject it.
+    #[serde(default = "default_structured_output")]
+    pub structured_output: bool,
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+}
+
+fn default_structured_output() -> bool {
+    true
+}
+
+fn default_request_timeout_secs() -> u64 {
+    300
+}
+
+/// Retries of transient failures (network errors, 408, 429, 5xx).
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64,
+    /// Also the longest `Retry-After` that is waited for.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30000,
+        }
+    }
+}
+
+/// Opens after `failure_threshold` consecutive failed requests and rejects
+/// calls for `open_secs`; a threshold of 0 disables it.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    pub failure_threshold: u32,
+    pub open_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_secs: 60,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -38,6 +94,202 @@ pub struct PromptsConfig {
     pub generate_grammar_rule_from_description: String,
 }
 
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    File,
+    Sqlite,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    /// Root of all stored data; other data paths left empty live under it.
+    pub data_dir: String,
+    pub sqlite_path: String,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::File,
+            data_dir: "data".to_owned(),
+            sqlite_path: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct DictionaryConfig {
+    pub index_path: String,
+    pub kanji_index_path: String,
+    /// Compiled Lindera IPADIC directory used to lemmatize words; empty
+    /// disables lemmatization.
+    pub morphology_path: String,
+    pub gloss_languages: Vec<String>,
+}
+
+impl Default for DictionaryConfig {
+    fn default() -> Self {
+        Self {
+            index_path: String::new(),
+            kanji_index_path: String::new(),
+            morphology_path: String::new(),
+            gloss_languages: vec!["rus".to_owned(), "eng".to_owned()],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum LlmProviderKind {
+    #[default]
+    #[serde(rename = "openrouter")]
+    OpenRouter,
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+    #[serde(rename = "mock")]
+    Mock,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LlmConfig {
+    pub provider: LlmProviderKind,
+    /// Re-prompts with the parse error before giving up on malformed JSON.
+    pub repair_attempts: u32,
+    pub cache: LlmCacheConfig,
+    pub local: LocalLlmConfig,
+    pub mock: MockLlmConfig,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self {
+            provider: LlmProviderKind::default(),
+            repair_attempts: 2,
+            cache: LlmCacheConfig::default(),
+            local: LocalLlmConfig::default(),
+            mock: MockLlmConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LlmCacheConfig {
+    pub enabled: bool,
+    pub dir: String,
+    pub ttl_secs: u64,
+    pub max_bytes: u64,
+}
+
+impl Default for LlmCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: String::new(),
+            ttl_secs: 30 * 24 * 60 * 60,
+            max_bytes: 100 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LocalLlmConfig {
+    pub base_url: String,
+    pub api_key: String,
+    pub text_model: String,
+    pub image_model: String,
+    pub reasoning_model: String,
+    pub max_tokens: u32,
+    pub structured_output: bool,
+    pub request_timeout_secs: u64,
+}
+
+impl Default for LocalLlmConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:11434/v1".to_owned(),
+            api_key: String::new(),
+            text_model: "qwen2.5:14b".to_owned(),
+            image_model: "gemma3:12b".to_owned(),
+            reasoning_model: "qwen3:14b".to_owned(),
+            max_tokens: 4096,
+            structured_output: true,
+            request_timeout_secs: default_request_timeout_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct MockLlmConfig {
+    pub fixtures_path: String,
+}
+
+impl Default for MockLlmConfig {
+    fn default() -> Self {
+        Self {
+            fixtures_path: "fixtures/llm.json".to_owned(),
+        }
+    }
+}
+
+/// Per-user LLM token limits; 0 means unlimited.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct QuotaConfig {
+    pub daily_tokens: u64,
+    pub monthly_tokens: u64,
+    /// Logins the limits do not apply to.
+    pub exempt_users: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct JobsConfig {
+    pub workers: usize,
+    pub max_attempts: u32,
+    /// Multiplied by the attempt number before a failed job runs again.
+    pub retry_delay_secs: u64,
+    /// Finished jobs older than this are removed on startup.
+    pub retention_days: u64,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            workers: 2,
+            max_attempts: 3,
+            retry_delay_secs: 30,
+            retention_days: 7,
+        }
+    }
+}
+
+/// How long texts are cut up before word extraction.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ExtractionConfig {
+    /// Characters per prompt; whole sentences are kept together.
+    pub chunk_chars: usize,
+    /// Chunks sent to the model at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for ExtractionConfig {
+    fn default() -> Self {
+        Self {
+            chunk_chars: 1500,
+            concurrency: 3,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Settings {
     pub server: ServerConfig,
@@ -45,6 +297,18 @@ pub struct Settings {
     pub jwt: JwtConfig,
     pub tls: Option<TlsConfig>,
     pub prompts: PromptsConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub dictionary: DictionaryConfig,
+    #[serde(default)]
+    pub llm: LlmConfig,
+    #[serde(default)]
+    pub quota: QuotaConfig,
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    #[serde(default)]
+    pub extraction: ExtractionConfig,
 }
 
 impl Settings {
@@ -58,6 +322,25 @@ impl Settings {
             )
             .build()?
             .try_deserialize::<Settings>()
+            .map(Settings::with_data_paths)
+    }
+
+    /// Places data paths that are not set explicitly under `data_dir`.
+    fn with_data_paths(mut self) -> Self {
+        let data_dir = Path::new(&self.storage.data_dir);
+        let default_to = |path: &mut String, relative: &str| {
+            if path.is_empty() {
+                *path = data_dir.join(relative).to_string_lossy().into_owned();
+            }
+        };
+        default_to(&mut self.storage.sqlite_path, "kanji_card.db");
+        default_to(&mut self.llm.cache.dir, "llm_cache");
+        default_to(&mut self.dictionary.index_path, "dictionary/jmdict.json");
+        default_to(
+            &mut self.dictionary.kanji_index_path,
+            "dictionary/kanjidic.json",
+        );
+        self
     }
 
     pub fn jwt_config(&self) -> crate::environment::auth::JwtConfig {
@@ -68,3 +351,25 @@ impl Settings {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support;
+
+    #[test]
+    fn unset_data_paths_follow_the_data_dir() {
+        let mut settings = test_support::settings();
+        settings.storage.data_dir = "/srv/kanji".to_owned();
+        settings.dictionary.index_path = "/opt/jmdict.json".to_owned();
+
+        let settings = settings.with_data_paths();
+
+        assert_eq!(settings.storage.sqlite_path, "/srv/kanji/kanji_card.db");
+        assert_eq!(settings.llm.cache.dir, "/srv/kanji/llm_cache");
+        assert_eq!(settings.dictionary.index_path, "/opt/jmdict.json");
+        assert_eq!(
+            settings.dictionary.kanji_index_path,
+            "/srv/kanji/dictionary/kanjidic.json"
+        );
+    }
+}
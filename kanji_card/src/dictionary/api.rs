@@ -0,0 +1,108 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+    http::StatusCode,
+    middleware,
+};
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    dictionary::{dictionary_service::DictionaryService, entry::DictionaryEntry},
+    environment::auth::{JwtConfig, auth_middleware},
+};
+
+pub fn query_router(dictionary: DictionaryService, jwt_config: JwtConfig) -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(lookup))
+        .layer(middleware::from_fn_with_state(
+            jwt_config.clone(),
+            auth_middleware,
+        ))
+        .with_state(dictionary)
+}
+
+#[derive(Deserialize, Debug)]
+struct LookupQuery {
+    word: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct DictionaryEntryResponse {
+    id: u64,
+    kanji: Vec<String>,
+    readings: Vec<String>,
+    common: bool,
+    senses: Vec<SenseResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SenseResponse {
+    pos: Vec<String>,
+    glosses: Vec<GlossResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct GlossResponse {
+    lang: String,
+    text: String,
+}
+
+impl From<DictionaryEntry> for DictionaryEntryResponse {
+    fn from(entry: DictionaryEntry) -> Self {
+        Self {
+            id: entry.id(),
+            kanji: entry.kanji().to_vec(),
+            readings: entry.readings().to_vec(),
+            common: entry.is_common(),
+            senses: entry
+                .senses()
+                .iter()
+                .map(|sense| SenseResponse {
+                    pos: sense.pos().to_vec(),
+                    glosses: sense
+                        .glosses()
+                        .iter()
+                        .map(|gloss| GlossResponse {
+                            lang: gloss.lang().to_owned(),
+                            text: gloss.text().to_owned(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/lookup",
+    params(
+        ("word" = String, Query, description = "Written form or reading to look up")
+    ),
+    responses(
+        (status = 200, description = "Matching dictionary entries, common words first", body = Vec<DictionaryEntryResponse>),
+        (status = 503, description = "Dictionary index is not imported")
+    )
+)]
+#[instrument(skip(dictionary))]
+async fn lookup(
+    State(dictionary): State<DictionaryService>,
+    Query(params): Query<LookupQuery>,
+) -> Result<Json<Vec<DictionaryEntryResponse>>, (StatusCode, String)> {
+    if !dictionary.is_loaded() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Dictionary is not imported".to_owned(),
+        ));
+    }
+
+    let entries = dictionary
+        .lookup(&params.word)
+        .into_iter()
+        .map(DictionaryEntryResponse::from)
+        .collect();
+    Ok(Json(entries))
+}
@@ -0,0 +1,351 @@
+use anyhow::Result;
+use serde::{Serialize, de::DeserializeOwned};
+use std::{collections::HashSet, path::Path, sync::Arc};
+use tokio::fs;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    config::DictionaryConfig,
+    dictionary::{
+        entry::{DictionaryEntry, DictionaryIndex},
+        jmdict,
+        kanji::{Kanji, KanjiIndex},
+        kanjidic,
+        morphology::{Lemma, Morphology, TextSegment},
+    },
+    llm::ExtractedWord,
+};
+
+/// Number of glosses joined into a single card translation.
+const MAX_TRANSLATION_GLOSSES: usize = 3;
+
+/// Default is the service without any index loaded.
+#[derive(Clone, Default)]
+pub struct DictionaryService {
+    index: Option<Arc<DictionaryIndex>>,
+    kanji: Option<Arc<KanjiIndex>>,
+    morphology: Option<Morphology>,
+    gloss_languages: Vec<String>,
+}
+
+impl DictionaryService {
+    /// Loads the prebuilt indexes. A missing index is not an error: the
+    /// service then answers its lookups with nothing.
+    pub async fn load(config: &DictionaryConfig) -> Result<Self> {
+        let index = read_index::<DictionaryIndex>(&config.index_path)
+            .await?
+            .map(|mut index| {
+                index.build_lookup();
+                info!("Loaded dictionary with {} entries", index.len());
+                Arc::new(index)
+            });
+        let kanji = read_index::<KanjiIndex>(&config.kanji_index_path)
+            .await?
+            .map(|mut index| {
+                index.build_lookup();
+                info!("Loaded kanji dictionary with {} entries", index.len());
+                Arc::new(index)
+            });
+        let morphology = Morphology::load(&config.morphology_path).await?;
+
+        Ok(Self {
+            index,
+            kanji,
+            morphology,
+            gloss_languages: config.gloss_languages.clone(),
+        })
+    }
+
+    /// Parses a JMdict XML file and writes the index used by [`Self::load`].
+    #[instrument(skip(config))]
+    pub async fn import_jmdict(source: &Path, config: &DictionaryConfig) -> Result<usize> {
+        info!("Importing JMdict from {}", source.display());
+        let source = source.to_owned();
+        let languages = config.gloss_languages.clone();
+
+        let index = tokio::task::spawn_blocking(move || -> Result<DictionaryIndex> {
+            let mut entries = jmdict::parse_file(&source)?;
+            for entry in entries.iter_mut() {
+                entry.retain_languages(&languages);
+            }
+            entries.retain(|x| !x.senses().is_empty());
+            Ok(DictionaryIndex::new(entries))
+        })
+        .await??;
+
+        let count = index.len();
+        write_index(&config.index_path, index).await?;
+        info!("Imported {} dictionary entries", count);
+        Ok(count)
+    }
+
+    /// Parses a KANJIDIC2 XML file and writes the kanji index.
+    #[instrument(skip(config))]
+    pub async fn import_kanjidic(source: &Path, config: &DictionaryConfig) -> Result<usize> {
+        info!("Importing KANJIDIC2 from {}", source.display());
+        let source = source.to_owned();
+
+        let index = tokio::task::spawn_blocking(move || -> Result<KanjiIndex> {
+            Ok(KanjiIndex::new(kanjidic::parse_file(&source)?))
+        })
+        .await??;
+
+        let count = index.len();
+        write_index(&config.kanji_index_path, index).await?;
+        info!("Imported {} kanji", count);
+        Ok(count)
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.index.is_some()
+    }
+
+    pub fn kanji(&self, literal: char) -> Option<Kanji> {
+        self.kanji.as_ref()?.get(literal).cloned()
+    }
+
+    /// Every known kanji, empty when KANJIDIC2 is not imported.
+    pub fn all_kanji(&self) -> &[Kanji] {
+        match &self.kanji {
+            Some(index) => index.all(),
+            None => &[],
+        }
+    }
+
+    pub fn lookup(&self, word: &str) -> Vec<DictionaryEntry> {
+        match &self.index {
+            Some(index) => index.lookup(word.trim()).into_iter().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn can_lemmatize(&self) -> bool {
+        self.morphology.is_some()
+    }
+
+    pub fn lemmatize(&self, word: &str) -> Option<Lemma> {
+        self.morphology.as_ref()?.lemmatize(word)
+    }
+
+    /// Words of `text` with their dictionary forms; `None` without morphology.
+    pub fn segment(&self, text: &str) -> Option<Vec<TextSegment>> {
+        Some(self.morphology.as_ref()?.segment(text))
+    }
+
+    /// Turns an inflected word into its dictionary form with the reading of
+    /// that form, and returns its part of speech when the analyzer knows it.
+    pub fn normalize(&self, mut word: ExtractedWord) -> (ExtractedWord, Option<String>) {
+        let Some(lemma) = self.lemmatize(&word.word) else {
+            return (word, None);
+        };
+        if lemma.base_form != word.word.trim() {
+            word.word = lemma.base_form;
+            word.reading = lemma.reading;
+        } else if word.reading.is_none() {
+            word.reading = lemma.reading;
+        }
+        (word, Some(lemma.part_of_speech))
+    }
+
+    /// Brings words to their dictionary form, then fills in readings and
+    /// missing translations from the dictionary. Words the dictionary does
+    /// not know are kept as they are.
+    pub fn annotate(&self, words: Vec<ExtractedWord>) -> Vec<ExtractedWord> {
+        let words = words.into_iter().map(|x| self.normalize(x).0);
+        let Some(index) = &self.index else {
+            return words.collect();
+        };
+
+        words
+            .map(|mut word| {
+                if let Some(entry) = index.lookup(word.word.trim()).first() {
+                    if word.reading.is_none() {
+                        word.reading = Self::reading_for(entry, word.word.trim());
+                    }
+                    if word.translation.trim().is_empty() {
+                        word.translation = self.translation(entry).unwrap_or_default();
+                    }
+                }
+                word
+            })
+            .collect()
+    }
+
+    /// Dictionary-only extraction used when the LLM is unavailable: greedy
+    /// longest match over runs of Japanese characters.
+    pub fn extract_words(&self, text: &str) -> Vec<ExtractedWord> {
+        let Some(index) = &self.index else {
+            return vec![];
+        };
+
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut seen = HashSet::new();
+        let mut result = vec![];
+        let mut position = 0;
+
+        while position < chars.len() {
+            if !is_japanese(chars[position]) {
+                position += 1;
+                continue;
+            }
+
+            let run_end = chars[position..]
+                .iter()
+                .position(|x| !is_japanese(*x))
+                .map_or(chars.len(), |x| position + x);
+            let longest = (run_end - position).min(index.max_form_len());
+
+            let matched = (1..=longest).rev().find_map(|len| {
+                let form = chars[position..position + len].iter().collect::<String>();
+                index.lookup(&form).first().map(|entry| (form, len, *entry))
+            });
+
+            match matched {
+                Some((form, len, entry)) => {
+                    if seen.insert(form.clone()) {
+                        result.push(ExtractedWord {
+                            reading: Self::reading_for(entry, &form),
+                            translation: self.translation(entry).unwrap_or_default(),
+                            word: form,
+                        });
+                    }
+                    position += len;
+                }
+                None => position += 1,
+            }
+        }
+
+        result
+    }
+
+    fn reading_for(entry: &DictionaryEntry, word: &str) -> Option<String> {
+        if entry.readings().iter().any(|x| x == word) {
+            return Some(word.to_owned());
+        }
+        entry.readings().first().cloned()
+    }
+
+    /// Glosses of the first configured language the entry has.
+    fn translation(&self, entry: &DictionaryEntry) -> Option<String> {
+        self.gloss_languages.iter().find_map(|lang| {
+            let glosses = entry
+                .senses()
+                .iter()
+                .flat_map(|x| x.glosses())
+                .filter(|x| x.lang() == lang)
+                .map(|x| x.text())
+                .take(MAX_TRANSLATION_GLOSSES)
+                .collect::<Vec<_>>();
+
+            (!glosses.is_empty()).then(|| glosses.join("; "))
+        })
+    }
+}
+
+async fn read_index<T>(path: &str) -> Result<Option<T>>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    if !Path::new(path).exists() {
+        warn!("Dictionary index {} not found, skipping it", path);
+        return Ok(None);
+    }
+
+    let path = path.to_owned();
+    let index = tokio::task::spawn_blocking(move || -> Result<T> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    })
+    .await??;
+    Ok(Some(index))
+}
+
+async fn write_index<T>(path: &str, index: T) -> Result<()>
+where
+    T: Serialize + Send + 'static,
+{
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let path = path.to_owned();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer(std::io::BufWriter::new(file), &index)?;
+        Ok(())
+    })
+    .await?
+}
+
+fn is_japanese(ch: char) -> bool {
+    matches!(ch,
+        '\u{3005}'
+        | '\u{3040}'..='\u{309F}'
+        | '\u{30A0}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quick_xml::Reader;
+
+    const JMDICT: &str = r#"<JMdict>
+<entry>
+<ent_seq>1</ent_seq>
+<k_ele><keb>猫</keb></k_ele>
+<r_ele><reb>ねこ</reb></r_ele>
+<sense>
+<gloss>cat</gloss>
+<gloss xml:lang="rus">кошка</gloss>
+</sense>
+</entry>
+<entry>
+<ent_seq>2</ent_seq>
+<k_ele><keb>犬</keb></k_ele>
+<r_ele><reb>いぬ</reb></r_ele>
+<sense>
+<gloss>dog</gloss>
+</sense>
+</entry>
+</JMdict>"#;
+
+    fn service(languages: &[&str]) -> DictionaryService {
+        let mut index = DictionaryIndex::new(jmdict::parse(Reader::from_str(JMDICT)).unwrap());
+        index.build_lookup();
+        DictionaryService {
+            index: Some(Arc::new(index)),
+            kanji: None,
+            morphology: None,
+            gloss_languages: languages.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    fn annotate(service: &DictionaryService, word: &str) -> ExtractedWord {
+        let word = ExtractedWord {
+            word: word.to_owned(),
+            translation: String::new(),
+            reading: None,
+        };
+        service.annotate(vec![word]).remove(0)
+    }
+
+    #[test]
+    fn translation_follows_the_gloss_language_preference() {
+        assert_eq!(
+            annotate(&service(&["rus", "eng"]), "猫").translation,
+            "кошка"
+        );
+        assert_eq!(annotate(&service(&["eng", "rus"]), "猫").translation, "cat");
+    }
+
+    #[test]
+    fn translation_falls_back_to_the_next_language() {
+        let word = annotate(&service(&["rus", "eng"]), "犬");
+
+        assert_eq!(word.translation, "dog");
+        assert_eq!(word.reading.as_deref(), Some("いぬ"));
+        assert_eq!(annotate(&service(&["rus"]), "犬").translation, "");
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DictionaryEntry {
+    id: u64,
+    kanji: Vec<String>,
+    readings: Vec<String>,
+    senses: Vec<Sense>,
+    common: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Sense {
+    pos: Vec<String>,
+    glosses: Vec<Gloss>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gloss {
+    lang: String,
+    text: String,
+}
+
+/// Serialized dictionary with an in-memory lookup table over every written
+/// form and reading.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DictionaryIndex {
+    entries: Vec<DictionaryEntry>,
+
+    #[serde(skip)]
+    forms: HashMap<String, Vec<usize>>,
+    #[serde(skip)]
+    max_form_len: usize,
+}
+
+impl DictionaryEntry {
+    pub fn new(
+        id: u64,
+        kanji: Vec<String>,
+        readings: Vec<String>,
+        senses: Vec<Sense>,
+        common: bool,
+    ) -> Self {
+        Self {
+            id,
+            kanji,
+            readings,
+            senses,
+            common,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn kanji(&self) -> &[String] {
+        &self.kanji
+    }
+
+    pub fn readings(&self) -> &[String] {
+        &self.readings
+    }
+
+    pub fn senses(&self) -> &[Sense] {
+        &self.senses
+    }
+
+    pub fn is_common(&self) -> bool {
+        self.common
+    }
+
+    /// Keeps only glosses in the given languages and drops senses left empty.
+    pub fn retain_languages(&mut self, languages: &[String]) {
+        for sense in self.senses.iter_mut() {
+            sense.glosses.retain(|x| languages.contains(&x.lang));
+        }
+        self.senses.retain(|x| !x.glosses.is_empty());
+    }
+}
+
+impl Sense {
+    pub fn new(pos: Vec<String>, glosses: Vec<Gloss>) -> Self {
+        Self { pos, glosses }
+    }
+
+    pub fn pos(&self) -> &[String] {
+        &self.pos
+    }
+
+    pub fn glosses(&self) -> &[Gloss] {
+        &self.glosses
+    }
+}
+
+impl Gloss {
+    pub fn new(lang: String, text: String) -> Self {
+        Self { lang, text }
+    }
+
+    pub fn lang(&self) -> &str {
+        &self.lang
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl DictionaryIndex {
+    pub fn new(entries: Vec<DictionaryEntry>) -> Self {
+        let mut index = Self {
+            entries,
+            forms: HashMap::new(),
+            max_form_len: 0,
+        };
+        index.build_lookup();
+        index
+    }
+
+    /// Rebuilds the lookup table after deserialization.
+    pub fn build_lookup(&mut self) {
+        self.forms.clear();
+        self.max_form_len = 0;
+
+        for (position, entry) in self.entries.iter().enumerate() {
+            for form in entry.kanji.iter().chain(entry.readings.iter()) {
+                let positions = self.forms.entry(form.clone()).or_default();
+                if !positions.contains(&position) {
+                    positions.push(position);
+                }
+                self.max_form_len = self.max_form_len.max(form.chars().count());
+            }
+        }
+
+        // Common words win when a form is ambiguous.
+        for positions in self.forms.values_mut() {
+            positions.sort_by_key(|x| !self.entries[*x].common);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn max_form_len(&self) -> usize {
+        self.max_form_len
+    }
+
+    pub fn lookup(&self, form: &str) -> Vec<&DictionaryEntry> {
+        self.forms
+            .get(form)
+            .map(|positions| positions.iter().map(|x| &self.entries[*x]).collect())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{Result, anyhow};
+use quick_xml::{Reader, events::Event};
+use std::{io::BufRead, path::Path};
+
+use crate::dictionary::entry::{DictionaryEntry, Gloss, Sense};
+
+/// Glosses without `xml:lang` are English.
+const DEFAULT_GLOSS_LANG: &str = "eng";
+
+/// Priority markers that JMdict uses for its "common word" flag.
+const COMMON_PRIORITIES: [&str; 5] = ["news1", "ichi1", "spec1", "spec2", "gai1"];
+
+#[derive(Default)]
+struct EntryBuilder {
+    id: u64,
+    kanji: Vec<String>,
+    readings: Vec<String>,
+    senses: Vec<Sense>,
+    common: bool,
+
+    pos: Vec<String>,
+    glosses: Vec<Gloss>,
+    gloss_lang: String,
+}
+
+impl EntryBuilder {
+    fn finish_sense(&mut self) {
+        // Part of speech carries over to following senses when omitted.
+        if self.pos.is_empty()
+            && let Some(previous) = self.senses.last()
+        {
+            self.pos = previous.pos().to_vec();
+        }
+
+        let pos = std::mem::take(&mut self.pos);
+        let glosses = std::mem::take(&mut self.glosses);
+        self.senses.push(Sense::new(pos, glosses));
+    }
+
+    fn build(self) -> DictionaryEntry {
+        DictionaryEntry::new(self.id, self.kanji, self.readings, self.senses, self.common)
+    }
+}
+
+pub fn parse_file(path: &Path) -> Result<Vec<DictionaryEntry>> {
+    let reader = Reader::from_file(path)?;
+    parse(reader)
+}
+
+/// Streams a JMdict XML document. Entity references declared in the DTD
+/// (`&n;`, `&v5r;`, ...) are kept as their short codes.
+pub fn parse<R: BufRead>(mut reader: Reader<R>) -> Result<Vec<DictionaryEntry>> {
+    let mut entries = vec![];
+    let mut buf = vec![];
+    let mut entry: Option<EntryBuilder> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                text.clear();
+                match e.name().as_ref() {
+                    b"entry" => entry = Some(EntryBuilder::default()),
+                    b"gloss" => {
+                        if let Some(entry) = entry.as_mut() {
+                            entry.gloss_lang = match e.try_get_attribute("xml:lang")? {
+                                Some(attr) => attr.unescape_value()?.into_owned(),
+                                None => DEFAULT_GLOSS_LANG.to_owned(),
+                            };
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(e) => text.push_str(&e.decode()?),
+            Event::CData(e) => text.push_str(&e.decode()?),
+            Event::GeneralRef(e) => {
+                if let Some(ch) = e.resolve_char_ref()? {
+                    text.push(ch);
+                } else {
+                    let name = e.decode()?;
+                    match name.as_ref() {
+                        "amp" => text.push('&'),
+                        "lt" => text.push('<'),
+                        "gt" => text.push('>'),
+                        "quot" => text.push('"'),
+                        "apos" => text.push('\''),
+                        other => text.push_str(other),
+                    }
+                }
+            }
+            Event::End(e) => {
+                let value = text.trim().to_owned();
+                text.clear();
+
+                if e.name().as_ref() == b"entry" {
+                    if let Some(entry) = entry.take() {
+                        entries.push(entry.build());
+                    }
+                } else if let Some(entry) = entry.as_mut() {
+                    match e.name().as_ref() {
+                        b"ent_seq" => {
+                            entry.id = value
+                                .parse()
+                                .map_err(|_| anyhow!("Invalid ent_seq: {}", value))?;
+                        }
+                        b"keb" => entry.kanji.push(value),
+                        b"reb" => entry.readings.push(value),
+                        b"ke_pri" | b"re_pri" if COMMON_PRIORITIES.contains(&value.as_str()) => {
+                            entry.common = true;
+                        }
+                        b"pos" => entry.pos.push(value),
+                        b"gloss" => {
+                            let lang = std::mem::take(&mut entry.gloss_lang);
+                            entry.glosses.push(Gloss::new(lang, value));
+                        }
+                        b"sense" => entry.finish_sense(),
+                        _ => {}
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JMDICT: &str = r#"<JMdict>
+<entry>
+<ent_seq>1000001</ent_seq>
+<k_ele><keb>食べる</keb><ke_pri>ichi1</ke_pri></k_ele>
+<r_ele><reb>たべる</reb></r_ele>
+<sense>
+<pos>&v1;</pos>
+<gloss>to eat</gloss>
+<gloss xml:lang="rus">есть</gloss>
+<gloss xml:lang="ger">essen</gloss>
+</sense>
+<sense>
+<gloss>to live on</gloss>
+</sense>
+</entry>
+<entry>
+<ent_seq>1000002</ent_seq>
+<r_ele><reb>ああ</reb><re_pri>spec9</re_pri></r_ele>
+<sense>
+<pos>&int;</pos>
+</sense>
+</entry>
+</JMdict>"#;
+
+    fn entries() -> Vec<DictionaryEntry> {
+        parse(Reader::from_str(JMDICT)).unwrap()
+    }
+
+    #[test]
+    fn parses_forms_priority_and_entity_codes() {
+        let entries = entries();
+        let taberu = &entries[0];
+
+        assert_eq!(taberu.id(), 1000001);
+        assert_eq!(taberu.kanji(), ["食べる"]);
+        assert_eq!(taberu.readings(), ["たべる"]);
+        assert!(taberu.is_common());
+        assert_eq!(taberu.senses()[0].pos(), ["v1"]);
+        assert_eq!(taberu.senses()[1].pos(), ["v1"]);
+        assert!(!entries[1].is_common());
+    }
+
+    #[test]
+    fn glosses_without_lang_are_english() {
+        let entries = entries();
+        let langs = entries[0].senses()[0]
+            .glosses()
+            .iter()
+            .map(|x| (x.lang(), x.text()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            langs,
+            [("eng", "to eat"), ("rus", "есть"), ("ger", "essen")]
+        );
+    }
+
+    #[test]
+    fn retaining_languages_drops_other_glosses_and_empty_senses() {
+        let mut entries = entries();
+        for entry in entries.iter_mut() {
+            entry.retain_languages(&["rus".to_owned()]);
+        }
+
+        assert_eq!(entries[0].senses().len(), 1);
+        assert_eq!(entries[0].senses()[0].glosses()[0].text(), "есть");
+        assert!(entries[1].kanji().is_empty());
+        assert!(entries[1].senses().is_empty());
+    }
+}
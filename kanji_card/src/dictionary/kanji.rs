@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Kanji {
+    literal: char,
+    meanings: Vec<String>,
+    on_readings: Vec<String>,
+    kun_readings: Vec<String>,
+    stroke_count: Option<u8>,
+    grade: Option<u8>,
+    jlpt: Option<u8>,
+    frequency: Option<u32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KanjiIndex {
+    kanji: Vec<Kanji>,
+
+    #[serde(skip)]
+    by_literal: HashMap<char, usize>,
+}
+
+impl Kanji {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        literal: char,
+        meanings: Vec<String>,
+        on_readings: Vec<String>,
+        kun_readings: Vec<String>,
+        stroke_count: Option<u8>,
+        grade: Option<u8>,
+        jlpt: Option<u8>,
+        frequency: Option<u32>,
+    ) -> Self {
+        Self {
+            literal,
+            meanings,
+            on_readings,
+            kun_readings,
+            stroke_count,
+            grade,
+            jlpt,
+            frequency,
+        }
+    }
+
+    pub fn literal(&self) -> char {
+        self.literal
+    }
+
+    pub fn meanings(&self) -> &[String] {
+        &self.meanings
+    }
+
+    pub fn on_readings(&self) -> &[String] {
+        &self.on_readings
+    }
+
+    pub fn kun_readings(&self) -> &[String] {
+        &self.kun_readings
+    }
+
+    pub fn stroke_count(&self) -> Option<u8> {
+        self.stroke_count
+    }
+
+    pub fn grade(&self) -> Option<u8> {
+        self.grade
+    }
+
+    /// Level from the old four-level JLPT (1 is the hardest).
+    pub fn jlpt(&self) -> Option<u8> {
+        self.jlpt
+    }
+
+    pub fn frequency(&self) -> Option<u32> {
+        self.frequency
+    }
+}
+
+impl KanjiIndex {
+    pub fn new(kanji: Vec<Kanji>) -> Self {
+        let mut index = Self {
+            kanji,
+            by_literal: HashMap::new(),
+        };
+        index.build_lookup();
+        index
+    }
+
+    /// Rebuilds the lookup table after deserialization.
+    pub fn build_lookup(&mut self) {
+        self.by_literal = self
+            .kanji
+            .iter()
+            .enumerate()
+            .map(|(position, kanji)| (kanji.literal, position))
+            .collect();
+    }
+
+    pub fn len(&self) -> usize {
+        self.kanji.len()
+    }
+
+    pub fn get(&self, literal: char) -> Option<&Kanji> {
+        self.by_literal.get(&literal).map(|x| &self.kanji[*x])
+    }
+
+    pub fn all(&self) -> &[Kanji] {
+        &self.kanji
+    }
+}
+
+pub fn is_kanji(ch: char) -> bool {
+    matches!(ch,
+        '\u{3005}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2A6DF}')
+}
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use quick_xml::{Reader, events::Event};
+use std::{io::BufRead, path::Path};
+
+use crate::dictionary::kanji::Kanji;
+
+#[derive(Default)]
+struct KanjiBuilder {
+    literal: Option<char>,
+    meanings: Vec<String>,
+    on_readings: Vec<String>,
+    kun_readings: Vec<String>,
+    stroke_count: Option<u8>,
+    grade: Option<u8>,
+    jlpt: Option<u8>,
+    frequency: Option<u32>,
+
+    reading_type: Option<String>,
+    english_meaning: bool,
+}
+
+impl KanjiBuilder {
+    fn build(self) -> Option<Kanji> {
+        Some(Kanji::new(
+            self.literal?,
+            self.meanings,
+            self.on_readings,
+            self.kun_readings,
+            self.stroke_count,
+            self.grade,
+            self.jlpt,
+            self.frequency,
+        ))
+    }
+}
+
+pub fn parse_file(path: &Path) -> Result<Vec<Kanji>> {
+    let reader = Reader::from_file(path)?;
+    parse(reader)
+}
+
+/// Streams a KANJIDIC2 XML document. Only English meanings are kept, the
+/// file has no Russian ones.
+pub fn parse<R: BufRead>(mut reader: Reader<R>) -> Result<Vec<Kanji>> {
+    let mut result = vec![];
+    let mut buf = vec![];
+    let mut character: Option<KanjiBuilder> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                text.clear();
+                match e.name().as_ref() {
+                    b"character" => character = Some(KanjiBuilder::default()),
+                    b"reading" => {
+                        if let Some(character) = character.as_mut() {
+                            character.reading_type = e
+                                .try_get_attribute("r_type")?
+                                .map(|x| x.unescape_value().map(|x| x.into_owned()))
+                                .transpose()?;
+                        }
+                    }
+                    b"meaning" => {
+                        if let Some(character) = character.as_mut() {
+                            character.english_meaning = e.try_get_attribute("m_lang")?.is_none();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(e) => text.push_str(&e.decode()?),
+            Event::GeneralRef(e) => {
+                if let Some(ch) = e.resolve_char_ref()? {
+                    text.push(ch);
+                } else {
+                    match e.decode()?.as_ref() {
+                        "amp" => text.push('&'),
+                        "lt" => text.push('<'),
+                        "gt" => text.push('>'),
+                        "quot" => text.push('"'),
+                        "apos" => text.push('\''),
+                        _ => {}
+                    }
+                }
+            }
+            Event::End(e) => {
+                let value = text.trim().to_owned();
+                text.clear();
+
+                if e.name().as_ref() == b"character" {
+                    if let Some(kanji) = character.take().and_then(KanjiBuilder::build) {
+                        result.push(kanji);
+                    }
+                } else if let Some(character) = character.as_mut() {
+                    match e.name().as_ref() {
+                        b"literal" => character.literal = value.chars().next(),
+                        // The first stroke count is the accepted one, the
+                        // rest are common miscounts.
+                        b"stroke_count" if character.stroke_count.is_none() => {
+                            character.stroke_count = value.parse().ok();
+                        }
+                        b"grade" => character.grade = value.parse().ok(),
+                        b"jlpt" => character.jlpt = value.parse().ok(),
+                        b"freq" => character.frequency = value.parse().ok(),
+                        b"reading" => match character.reading_type.take().as_deref() {
+                            Some("ja_on") => character.on_readings.push(value),
+                            Some("ja_kun") => character.kun_readings.push(value),
+                            _ => {}
+                        },
+                        b"meaning" if character.english_meaning => character.meanings.push(value),
+                        _ => {}
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KANJIDIC: &str = r#"<kanjidic2>
+<character>
+<literal>日</literal>
+<misc><grade>1</grade><stroke_count>4</stroke_count><stroke_count>5</stroke_count><freq>1</freq><jlpt>4</jlpt></misc>
+<reading_meaning><rmgroup>
+<reading r_type="pinyin">ri4</reading>
+<reading r_type="ja_on">ニチ</reading>
+<reading r_type="ja_kun">ひ</reading>
+<meaning>day</meaning>
+<meaning>sun</meaning>
+<meaning m_lang="fr">jour</meaning>
+</rmgroup></reading_meaning>
+</character>
+<character>
+<literal>々</literal>
+<misc><stroke_count>3</stroke_count></misc>
+</character>
+<character>
+<misc><stroke_count>1</stroke_count></misc>
+</character>
+</kanjidic2>"#;
+
+    fn kanji() -> Vec<Kanji> {
+        parse(Reader::from_str(KANJIDIC)).unwrap()
+    }
+
+    #[test]
+    fn keeps_english_meanings_and_japanese_readings() {
+        let kanji = kanji();
+        let day = &kanji[0];
+
+        assert_eq!(day.literal(), '日');
+        assert_eq!(day.meanings(), ["day", "sun"]);
+        assert_eq!(day.on_readings(), ["ニチ"]);
+        assert_eq!(day.kun_readings(), ["ひ"]);
+        assert_eq!(day.stroke_count(), Some(4));
+        assert_eq!(day.grade(), Some(1));
+        assert_eq!(day.jlpt(), Some(4));
+        assert_eq!(day.frequency(), Some(1));
+    }
+
+    #[test]
+    fn keeps_characters_without_readings_or_meanings() {
+        let kanji = kanji();
+
+        assert_eq!(kanji.len(), 2);
+        assert_eq!(kanji[1].literal(), '々');
+        assert!(kanji[1].meanings().is_empty());
+        assert!(kanji[1].on_readings().is_empty());
+        assert_eq!(kanji[1].grade(), None);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod api;
+pub mod dictionary_service;
+pub mod entry;
+pub mod jmdict;
+pub mod kanji;
+pub mod kanjidic;
+pub mod morphology;
@@ -0,0 +1,310 @@
+use anyhow::{Result, anyhow};
+use lindera::{dictionary::load_dictionary, mode::Mode, segmenter::Segmenter, token::Token};
+use std::{borrow::Cow, path::Path, sync::Arc};
+use tracing::{info, warn};
+
+/// Parts of speech that only inflect the word before them.
+const INFLECTION_POS: [&str; 2] = ["助動詞", "助詞"];
+const SYMBOL_POS: &str = "記号";
+
+/// Dictionary form of a word as found by the morphological analyzer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lemma {
+    pub base_form: String,
+    /// Hiragana reading of the dictionary form.
+    pub reading: Option<String>,
+    pub part_of_speech: String,
+}
+
+/// Word of a text with the particles and auxiliaries that follow it;
+/// punctuation and text that could not be segmented have no `lemma`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSegment {
+    pub surface: String,
+    pub lemma: Option<Lemma>,
+}
+
+/// Lindera segmenter over an IPADIC-style dictionary.
+#[derive(Clone)]
+pub struct Morphology {
+    segmenter: Arc<Segmenter>,
+}
+
+struct Morpheme {
+    surface: String,
+    base_form: String,
+    reading: Option<String>,
+    part_of_speech: String,
+}
+
+impl Morphology {
+    /// Loads a compiled Lindera dictionary; `None` when the path is empty.
+    /// No dictionary is bundled, so a configured path that does not exist is
+    /// an error rather than a silently disabled lemmatizer.
+    pub async fn load(path: &str) -> Result<Option<Self>> {
+        if path.is_empty() {
+            warn!("No morphological dictionary configured, words are not lemmatized");
+            return Ok(None);
+        }
+        if !path.contains("://") && !Path::new(path).exists() {
+            return Err(anyhow!(
+                "Morphological dictionary {} not found; build Lindera IPADIC there or set dictionary.morphology_path to \"\"",
+                path
+            ));
+        }
+
+        let path = path.to_owned();
+        let segmenter = tokio::task::spawn_blocking(move || -> Result<Segmenter> {
+            let dictionary = load_dictionary(&path)
+                .map_err(|e| anyhow!("Failed to load morphological dictionary: {}", e))?;
+            Ok(Segmenter::new(Mode::Normal, dictionary, None))
+        })
+        .await??;
+        info!("Loaded morphological dictionary");
+
+        Ok(Some(Self {
+            segmenter: Arc::new(segmenter),
+        }))
+    }
+
+    fn analyze(&self, text: &str) -> Vec<Morpheme> {
+        let tokens = match self.segmenter.segment(Cow::Borrowed(text)) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                warn!("Failed to segment {}: {}", text, e);
+                return vec![];
+            }
+        };
+        tokens.into_iter().map(Morpheme::from).collect()
+    }
+
+    /// Dictionary form of a single word, e.g. 食べた and 食べます become
+    /// 食べる and 勉強した becomes 勉強する. Phrases of several content words
+    /// are left alone.
+    pub fn lemmatize(&self, word: &str) -> Option<Lemma> {
+        self.lemma_of(&self.analyze(word.trim()))
+    }
+
+    /// Cuts a text into words, e.g. 猫が勉強した into 猫が and 勉強した.
+    /// The surfaces of the segments add up to the text.
+    pub fn segment(&self, text: &str) -> Vec<TextSegment> {
+        let morphemes = self.analyze(text);
+        if morphemes.is_empty() {
+            return vec![TextSegment {
+                surface: text.to_owned(),
+                lemma: None,
+            }];
+        }
+
+        let mut groups = vec![];
+        let mut start = 0;
+        for (index, morpheme) in morphemes.iter().enumerate().skip(1) {
+            let head = &morphemes[start];
+            let is_suru_verb =
+                index == start + 1 && head.part_of_speech == "名詞" && morpheme.base_form == "する";
+            if !(head.is_word() && (is_suru_verb || morpheme.is_inflection())) {
+                groups.push(&morphemes[start..index]);
+                start = index;
+            }
+        }
+        groups.push(&morphemes[start..]);
+
+        groups
+            .into_iter()
+            .map(|group| TextSegment {
+                surface: group.iter().map(|x| x.surface.as_str()).collect(),
+                lemma: group[0].is_word().then(|| self.lemma_of(group)).flatten(),
+            })
+            .collect()
+    }
+
+    fn lemma_of(&self, morphemes: &[Morpheme]) -> Option<Lemma> {
+        let (head, rest) = morphemes.split_first()?;
+
+        let (base_form, part_of_speech, inflections) = match rest.split_first() {
+            Some((next, tail)) if head.part_of_speech == "名詞" && next.base_form == "する" => {
+                (format!("{}する", head.surface), "動詞".to_owned(), tail)
+            }
+            _ => (head.base_form.clone(), head.part_of_speech.clone(), rest),
+        };
+        if !inflections.iter().all(Morpheme::is_inflection) {
+            return None;
+        }
+
+        let readings = self
+            .analyze(&base_form)
+            .into_iter()
+            .map(|x| x.reading)
+            .collect::<Option<Vec<_>>>();
+        Some(Lemma {
+            reading: readings.map(|x| to_hiragana(&x.concat())),
+            base_form,
+            part_of_speech,
+        })
+    }
+}
+
+impl Morpheme {
+    fn is_inflection(&self) -> bool {
+        INFLECTION_POS.contains(&self.part_of_speech.as_str())
+    }
+
+    /// Neither an inflection nor punctuation.
+    fn is_word(&self) -> bool {
+        !self.is_inflection() && self.part_of_speech != SYMBOL_POS
+    }
+}
+
+impl From<Token<'_>> for Morpheme {
+    fn from(mut token: Token<'_>) -> Self {
+        let surface = token.surface.to_string();
+        let mut field = |name: &str| {
+            token
+                .get(name)
+                .filter(|x| !x.is_empty() && *x != "*")
+                .map(str::to_owned)
+        };
+        let part_of_speech = field("major_pos").unwrap_or_default();
+        let base_form = field("base_form").unwrap_or_else(|| surface.clone());
+        let reading = field("reading");
+        Self {
+            surface,
+            base_form,
+            reading,
+            part_of_speech,
+        }
+    }
+}
+
+fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            'ァ'..='ヶ' => char::from_u32(ch as u32 - 0x60).unwrap_or(ch),
+            _ => ch,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::morphology_dictionary;
+    use tempfile::TempDir;
+
+    async fn morphology(dir: &TempDir) -> Morphology {
+        Morphology::load(&morphology_dictionary(dir.path()))
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    fn surfaces(segments: &[TextSegment]) -> Vec<&str> {
+        segments.iter().map(|x| x.surface.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn segments_keep_particles_and_inflections_with_their_word() {
+        let dir = TempDir::new().unwrap();
+        let morphology = morphology(&dir).await;
+
+        let segments = morphology.segment("猫が勉強した。犬を食べた");
+
+        assert_eq!(
+            surfaces(&segments),
+            ["猫が", "勉強した", "。", "犬を", "食べた"]
+        );
+        let base_forms = segments
+            .iter()
+            .map(|x| x.lemma.as_ref().map(|x| x.base_form.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            base_forms,
+            [
+                Some("猫"),
+                Some("勉強する"),
+                None,
+                Some("犬"),
+                Some("食べる")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn leading_particle_has_no_lemma() {
+        let dir = TempDir::new().unwrap();
+        let morphology = morphology(&dir).await;
+
+        let segments = morphology.segment("が猫");
+
+        assert_eq!(surfaces(&segments), ["が", "猫"]);
+        assert_eq!(segments[0].lemma, None);
+    }
+
+    fn lemma(base_form: &str, reading: &str, part_of_speech: &str) -> Option<Lemma> {
+        Some(Lemma {
+            base_form: base_form.to_owned(),
+            reading: Some(reading.to_owned()),
+            part_of_speech: part_of_speech.to_owned(),
+        })
+    }
+
+    #[tokio::test]
+    async fn inflected_verb_becomes_its_dictionary_form() {
+        let dir = TempDir::new().unwrap();
+        let morphology = morphology(&dir).await;
+
+        assert_eq!(
+            morphology.lemmatize("食べた"),
+            lemma("食べる", "たべる", "動詞")
+        );
+        assert_eq!(
+            morphology.lemmatize("食べます"),
+            lemma("食べる", "たべる", "動詞")
+        );
+    }
+
+    #[tokio::test]
+    async fn noun_with_suru_becomes_a_verb() {
+        let dir = TempDir::new().unwrap();
+        let morphology = morphology(&dir).await;
+
+        assert_eq!(
+            morphology.lemmatize("勉強した"),
+            lemma("勉強する", "べんきょうする", "動詞")
+        );
+        assert_eq!(
+            morphology.lemmatize(" 勉強 "),
+            lemma("勉強", "べんきょう", "名詞")
+        );
+    }
+
+    #[tokio::test]
+    async fn phrases_are_not_lemmatized() {
+        let dir = TempDir::new().unwrap();
+        let morphology = morphology(&dir).await;
+
+        assert_eq!(morphology.lemmatize("猫が食べた"), None);
+        assert_eq!(morphology.lemmatize("猫犬"), None);
+        assert_eq!(morphology.lemmatize(""), None);
+    }
+
+    #[tokio::test]
+    async fn empty_path_disables_morphology() {
+        assert!(Morphology::load("").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_dictionary_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("ipadic");
+
+        assert!(Morphology::load(&path.to_string_lossy()).await.is_err());
+    }
+
+    #[test]
+    fn to_hiragana_folds_katakana_only() {
+        assert_eq!(to_hiragana("ベンキョウスル"), "べんきょうする");
+        assert_eq!(to_hiragana("ヴァイオリン"), "ゔぁいおりん");
+        assert_eq!(to_hiragana("カード・ｶﾅ 漢字"), "かーど・ｶﾅ 漢字");
+    }
+}
@@ -2,6 +2,7 @@ use crate::{
     environment::auth,
     llm::ExtractedWord,
     rule::{rule::JapanesePartOfSpeech, rule_service::RuleService},
+    usage::api::llm_error_status,
 };
 use auth::{Claims, JwtConfig, auth_middleware};
 use axum::{
@@ -21,7 +22,7 @@ struct ApiState {
     rule_service: Arc<RuleService>,
 }
 
-pub fn set_api_router(rule_service: RuleService, jwt_config: JwtConfig) -> OpenApiRouter {
+pub fn set_api_router(rule_service: Arc<RuleService>, jwt_config: JwtConfig) -> OpenApiRouter {
     OpenApiRouter::new()
         .routes(routes!(create_rule_from_text))
         .routes(routes!(create_rule_from_description))
@@ -32,9 +33,7 @@ pub fn set_api_router(rule_service: RuleService, jwt_config: JwtConfig) -> OpenA
             jwt_config.clone(),
             auth_middleware,
         ))
-        .with_state(ApiState {
-            rule_service: Arc::new(rule_service),
-        })
+        .with_state(ApiState { rule_service })
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
@@ -110,6 +109,7 @@ struct CreateRuleResponse {
     request_body = CreateRuleFromTextRequest,
     responses(
         (status = 200, description = "Grammar rule created successfully", body = CreateRuleResponse),
+        (status = 429, description = "LLM quota exceeded"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -137,7 +137,7 @@ async fn create_rule_from_text(
         }
         Err(e) => {
             error!("Failed to create grammar rule from text: {}", e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            Err((llm_error_status(&e), e.to_string()))
         }
     }
 }
@@ -148,6 +148,7 @@ async fn create_rule_from_text(
     request_body = CreateRuleFromDescriptionRequest,
     responses(
         (status = 200, description = "Grammar rule created successfully", body = CreateRuleResponse),
+        (status = 429, description = "LLM quota exceeded"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -175,7 +176,7 @@ async fn create_rule_from_description(
         }
         Err(e) => {
             error!("Failed to create grammar rule from description: {}", e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            Err((llm_error_status(&e), e.to_string()))
         }
     }
 }
@@ -41,7 +41,7 @@ struct ErrorResponse {
 }
 
 pub async fn login(
-    user_repo: Arc<UserRepository>,
+    user_repo: Arc<dyn UserRepository>,
     jwt_config: Arc<JwtConfig>,
     login: &str,
     password: &str,
@@ -118,7 +118,7 @@ pub async fn login(
 }
 
 pub async fn register(
-    user_repo: Arc<UserRepository>,
+    user_repo: Arc<dyn UserRepository>,
     login: &str,
     password: &str,
 ) -> Result<Response, Response> {
@@ -13,17 +13,17 @@ use crate::{
 
 #[derive(Clone)]
 struct AuthApiState {
-    repository: Arc<UserRepository>,
+    repository: Arc<dyn UserRepository>,
     jwt_config: Arc<JwtConfig>,
 }
 
-pub fn jwt_api_router(user_repo: UserRepository, jwt_config: JwtConfig) -> OpenApiRouter {
+pub fn jwt_api_router(user_repo: Arc<dyn UserRepository>, jwt_config: JwtConfig) -> OpenApiRouter {
     OpenApiRouter::new()
         .routes(routes!(login))
         .routes(routes!(register))
         .routes(routes!(logout))
         .with_state(AuthApiState {
-            repository: Arc::new(user_repo),
+            repository: user_repo,
             jwt_config: Arc::new(jwt_config),
         })
 }
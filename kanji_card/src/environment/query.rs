@@ -18,10 +18,13 @@ use crate::{
 
 #[derive(Clone)]
 struct QueryState {
-    rule_repository: Arc<RuleRepository>,
+    rule_repository: Arc<dyn RuleRepository>,
 }
 
-pub fn query_router(rule_repository: RuleRepository, jwt_config: JwtConfig) -> OpenApiRouter {
+pub fn query_router(
+    rule_repository: Arc<dyn RuleRepository>,
+    jwt_config: JwtConfig,
+) -> OpenApiRouter {
     OpenApiRouter::new()
         .routes(routes!(list_rules))
         .routes(routes!(get_rule))
@@ -29,9 +32,7 @@ pub fn query_router(rule_repository: RuleRepository, jwt_config: JwtConfig) -> O
             jwt_config.clone(),
             auth_middleware,
         ))
-        .with_state(QueryState {
-            rule_repository: Arc::new(rule_repository),
-        })
+        .with_state(QueryState { rule_repository })
 }
 
 #[derive(Serialize, ToSchema)]
@@ -0,0 +1,308 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+    middleware,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::{convert::Infallible, sync::Arc};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, instrument, warn};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    environment::auth::{Claims, JwtConfig, auth_middleware},
+    job::{
+        domain::{Job, JobEvent, JobKind, JobResult, JobStatus},
+        job_service::JobService,
+    },
+};
+
+#[derive(Clone)]
+struct ApiState {
+    job_service: Arc<JobService>,
+}
+
+pub fn job_api_router(job_service: Arc<JobService>, jwt_config: JwtConfig) -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(enqueue_extract_text))
+        .routes(routes!(enqueue_extract_image))
+        .routes(routes!(enqueue_rule_from_text))
+        .routes(routes!(enqueue_rule_from_description))
+        .routes(routes!(list_jobs))
+        .routes(routes!(job_events))
+        .routes(routes!(get_job))
+        .layer(middleware::from_fn_with_state(
+            jwt_config.clone(),
+            auth_middleware,
+        ))
+        .with_state(ApiState { job_service })
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct ExtractTextJobRequest {
+    text: String,
+    #[serde(default)]
+    bypass_cache: bool,
+    /// Leaves out words already in a set or released.
+    #[serde(default)]
+    skip_known: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct ExtractImageJobRequest {
+    image_data: Vec<u8>,
+    #[serde(default)]
+    bypass_cache: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct RuleFromTextJobRequest {
+    text: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct RuleFromDescriptionJobRequest {
+    description: String,
+}
+
+#[derive(Serialize, ToSchema, Debug)]
+struct JobResponse {
+    id: String,
+    kind: String,
+    status: JobStatus,
+    attempts: u32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    /// Set once the job succeeded.
+    result: Option<JobResult>,
+    /// Last failure; kept while a retry is queued.
+    error: Option<String>,
+}
+
+impl From<&Job> for JobResponse {
+    fn from(job: &Job) -> Self {
+        Self {
+            id: job.id().to_owned(),
+            kind: job.kind().name().to_owned(),
+            status: job.status(),
+            attempts: job.attempts(),
+            created_at: job.created_at(),
+            updated_at: job.updated_at(),
+            result: job.result().cloned(),
+            error: job.error().map(str::to_owned),
+        }
+    }
+}
+
+async fn enqueue(
+    state: &ApiState,
+    claims: &Claims,
+    kind: JobKind,
+) -> Result<(StatusCode, Json<JobResponse>), (StatusCode, String)> {
+    match state.job_service.enqueue(&claims.sub, kind).await {
+        Ok(job) => Ok((StatusCode::ACCEPTED, Json(JobResponse::from(&job)))),
+        Err(e) => {
+            error!("Failed to enqueue job for {}: {}", claims.sub, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/extract/text",
+    request_body = ExtractTextJobRequest,
+    responses(
+        (status = 202, description = "Extraction queued", body = JobResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn enqueue_extract_text(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ExtractTextJobRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), (StatusCode, String)> {
+    info!("Queueing word extraction from text for {}", claims.sub);
+    let kind = JobKind::ExtractWordsFromText {
+        text: request.text,
+        bypass_cache: request.bypass_cache,
+        skip_known: request.skip_known,
+    };
+    enqueue(&state, &claims, kind).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/extract/image",
+    request_body = ExtractImageJobRequest,
+    responses(
+        (status = 202, description = "Extraction queued", body = JobResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn enqueue_extract_image(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ExtractImageJobRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), (StatusCode, String)> {
+    info!("Queueing word extraction from image for {}", claims.sub);
+    let kind = JobKind::ExtractWordsFromImage {
+        image_data: request.image_data,
+        bypass_cache: request.bypass_cache,
+    };
+    enqueue(&state, &claims, kind).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/rule/text",
+    request_body = RuleFromTextJobRequest,
+    responses(
+        (status = 202, description = "Rule generation queued", body = JobResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn enqueue_rule_from_text(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<RuleFromTextJobRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), (StatusCode, String)> {
+    info!("Queueing grammar rule from text for {}", claims.sub);
+    let kind = JobKind::CreateRuleFromText { text: request.text };
+    enqueue(&state, &claims, kind).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/rule/description",
+    request_body = RuleFromDescriptionJobRequest,
+    responses(
+        (status = 202, description = "Rule generation queued", body = JobResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn enqueue_rule_from_description(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<RuleFromDescriptionJobRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), (StatusCode, String)> {
+    info!("Queueing grammar rule from description for {}", claims.sub);
+    let kind = JobKind::CreateRuleFromDescription {
+        description: request.description,
+    };
+    enqueue(&state, &claims, kind).await
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    responses(
+        (status = 200, description = "Jobs of the current user, newest first", body = Vec<JobResponse>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn list_jobs(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<JobResponse>>, (StatusCode, String)> {
+    match state.job_service.list(&claims.sub).await {
+        Ok(jobs) => Ok(Json(jobs.iter().map(JobResponse::from).collect())),
+        Err(e) => {
+            error!("Failed to list jobs of {}: {}", claims.sub, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, description = "Job status and result", body = JobResponse),
+        (status = 404, description = "Job not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn get_job(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+) -> Result<Json<JobResponse>, (StatusCode, String)> {
+    match state.job_service.get(&claims.sub, &id).await {
+        Ok(Some(job)) => Ok(Json(JobResponse::from(&job))),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("Job {id} not found"))),
+        Err(e) => {
+            error!("Failed to load job {} of {}: {}", id, claims.sub, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/events",
+    responses(
+        (status = 200, description = "Server-sent events of the current user's jobs: `status`, `words`, `succeeded`, `failed`, and `lagged` when events were dropped", content_type = "text/event-stream", body = JobEvent)
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn job_events(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    info!("Streaming job events to {}", claims.sub);
+    // Subscribe before reading the snapshot so no change falls in between.
+    let receiver = state.job_service.subscribe();
+    let snapshot = match state.job_service.list(&claims.sub).await {
+        Ok(jobs) => jobs
+            .iter()
+            .filter(|x| !x.status().is_finished())
+            .map(|x| Ok(sse_event(&JobEvent::status(x))))
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            error!("Failed to list jobs of {}: {}", claims.sub, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+        }
+    };
+
+    let user_login = claims.sub;
+    let events = stream::unfold(receiver, move |mut receiver| {
+        let user_login = user_login.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if event.user_login == user_login => {
+                        return Some((Ok(sse_event(&event)), receiver));
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Job event stream of {} lagged by {}", user_login, skipped);
+                        let event = Event::default().event("lagged").data(skipped.to_string());
+                        return Some((Ok(event), receiver));
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    Ok(Sse::new(stream::iter(snapshot).chain(events)).keep_alive(KeepAlive::default()))
+}
+
+fn sse_event(event: &JobEvent) -> Event {
+    Event::default()
+        .event(event.kind.name())
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
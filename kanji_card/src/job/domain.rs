@@ -0,0 +1,272 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+use utoipa::ToSchema;
+
+use crate::llm::ExtractedWord;
+
+/// LLM work that runs in the background instead of inside the request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobKind {
+    ExtractWordsFromText {
+        text: String,
+        bypass_cache: bool,
+        #[serde(default)]
+        skip_known: bool,
+    },
+    ExtractWordsFromImage {
+        #[serde(with = "base64_bytes")]
+        image_data: Vec<u8>,
+        bypass_cache: bool,
+    },
+    CreateRuleFromText {
+        text: String,
+    },
+    CreateRuleFromDescription {
+        description: String,
+    },
+}
+
+impl JobKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobKind::ExtractWordsFromText { .. } => "extract_words_from_text",
+            JobKind::ExtractWordsFromImage { .. } => "extract_words_from_image",
+            JobKind::CreateRuleFromText { .. } => "create_rule_from_text",
+            JobKind::CreateRuleFromDescription { .. } => "create_rule_from_description",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobResult {
+    Words {
+        words: Vec<ExtractedWord>,
+        /// Words skipped because the user already has them.
+        #[serde(default)]
+        known_words: Vec<String>,
+    },
+    Rule {
+        rule_id: String,
+        title: String,
+    },
+}
+
+/// What happened to a job, as streamed to its owner.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobEventKind {
+    Status {
+        status: JobStatus,
+        attempts: u32,
+        error: Option<String>,
+    },
+    /// Words extracted so far; later events carry further words only.
+    Words {
+        words: Vec<ExtractedWord>,
+    },
+    Succeeded {
+        result: JobResult,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl JobEventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobEventKind::Status { .. } => "status",
+            JobEventKind::Words { .. } => "words",
+            JobEventKind::Succeeded { .. } => "succeeded",
+            JobEventKind::Failed { .. } => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobEvent {
+    pub job_id: String,
+    #[serde(skip)]
+    pub user_login: String,
+    #[serde(flatten)]
+    pub kind: JobEventKind,
+}
+
+impl JobEvent {
+    pub fn new(job: &Job, kind: JobEventKind) -> Self {
+        Self {
+            job_id: job.id().to_owned(),
+            user_login: job.user_login().to_owned(),
+            kind,
+        }
+    }
+
+    pub fn status(job: &Job) -> Self {
+        Self::new(
+            job,
+            JobEventKind::Status {
+                status: job.status(),
+                attempts: job.attempts(),
+                error: job.error().map(str::to_owned),
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    id: String,
+    user_login: String,
+    kind: JobKind,
+    status: JobStatus,
+    attempts: u32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    /// Earliest time a queued retry may run.
+    #[serde(default)]
+    run_after: Option<DateTime<Utc>>,
+    result: Option<JobResult>,
+    error: Option<String>,
+}
+
+impl Job {
+    pub fn new(user_login: &str, kind: JobKind) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Ulid::new().to_string(),
+            user_login: user_login.to_owned(),
+            kind,
+            status: JobStatus::Queued,
+            attempts: 0,
+            created_at: now,
+            updated_at: now,
+            run_after: None,
+            result: None,
+            error: None,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn user_login(&self) -> &str {
+        &self.user_login
+    }
+
+    pub fn kind(&self) -> &JobKind {
+        &self.kind
+    }
+
+    pub fn status(&self) -> JobStatus {
+        self.status
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    pub fn run_after(&self) -> Option<DateTime<Utc>> {
+        self.run_after
+    }
+
+    pub fn result(&self) -> Option<&JobResult> {
+        self.result.as_ref()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn start(&mut self) {
+        self.status = JobStatus::Running;
+        self.attempts += 1;
+        self.run_after = None;
+        self.updated_at = Utc::now();
+    }
+
+    pub fn succeed(&mut self, result: JobResult) {
+        self.status = JobStatus::Succeeded;
+        self.result = Some(result);
+        self.error = None;
+        self.updated_at = Utc::now();
+    }
+
+    pub fn fail(&mut self, error: String) {
+        self.status = JobStatus::Failed;
+        self.error = Some(error);
+        self.updated_at = Utc::now();
+    }
+
+    /// Puts the job back into the queue, keeping the last error visible.
+    pub fn requeue(&mut self, error: Option<String>, run_after: Option<DateTime<Utc>>) {
+        self.status = JobStatus::Queued;
+        self.error = error;
+        self.run_after = run_after;
+        self.updated_at = Utc::now();
+    }
+}
+
+/// Keeps persisted images compact as base64 text.
+mod base64_bytes {
+    use base64::{Engine, engine::general_purpose};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&general_purpose::STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        general_purpose::STANDARD
+            .decode(text)
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_is_stored_as_base64() {
+        let kind = JobKind::ExtractWordsFromImage {
+            image_data: vec![0xff, 0xd8, 0xff, 0xe0],
+            bypass_cache: false,
+        };
+
+        let json = serde_json::to_value(&kind).unwrap();
+
+        assert_eq!(json["image_data"], "/9j/4A==");
+        assert!(matches!(
+            serde_json::from_value(json).unwrap(),
+            JobKind::ExtractWordsFromImage { image_data, .. } if image_data == [0xff, 0xd8, 0xff, 0xe0]
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{OptionalExtension, params};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::{job::domain::Job, storage::SqliteStorage};
+
+const STORAGE_DIR: &str = "job";
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+#[async_trait]
+pub trait JobRepository: Send + Sync {
+    async fn save(&self, job: &Job) -> anyhow::Result<()>;
+
+    async fn get(&self, user_login: &str, id: &str) -> anyhow::Result<Option<Job>>;
+
+    /// Jobs of the user, newest first.
+    async fn list(&self, user_login: &str) -> anyhow::Result<Vec<Job>>;
+
+    /// Queued and running jobs of every user, oldest first.
+    async fn list_unfinished(&self) -> anyhow::Result<Vec<Job>>;
+
+    /// Removes finished jobs last updated before `before`.
+    async fn delete_finished_before(&self, before: DateTime<Utc>) -> anyhow::Result<usize>;
+}
+
+/// Keeps every job in its own file, so workers of different users never
+/// rewrite the same file.
+#[derive(Clone)]
+pub struct FileJobRepository {
+    storage_dir: PathBuf,
+}
+
+impl FileJobRepository {
+    pub async fn new(data_dir: &Path) -> anyhow::Result<Self> {
+        let storage_dir = data_dir.join(STORAGE_DIR);
+        fs::create_dir_all(&storage_dir).await?;
+
+        Ok(Self { storage_dir })
+    }
+
+    fn get_job_path(&self, id: &str) -> PathBuf {
+        self.storage_dir.join(format!("{id}.json"))
+    }
+
+    async fn load_all(&self) -> anyhow::Result<Vec<Job>> {
+        let mut jobs = Vec::new();
+        let mut entries = fs::read_dir(&self.storage_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry
+                .file_name()
+                .to_str()
+                .is_some_and(|x| x.ends_with(".json"))
+            {
+                let json = fs::read_to_string(entry.path()).await?;
+                jobs.push(serde_json::from_str::<Job>(&json)?);
+            }
+        }
+        jobs.sort_by(|a, b| a.id().cmp(b.id()));
+        Ok(jobs)
+    }
+}
+
+#[async_trait]
+impl JobRepository for FileJobRepository {
+    async fn save(&self, job: &Job) -> anyhow::Result<()> {
+        let json = serde_json::to_string(job)?;
+        let file_path = self.get_job_path(job.id());
+        let tmp_path = file_path.with_extension("json.tmp");
+        fs::write(&tmp_path, json).await?;
+        fs::rename(tmp_path, file_path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, user_login: &str, id: &str) -> anyhow::Result<Option<Job>> {
+        let file_path = self.get_job_path(id);
+        if !file_path.exists() {
+            return Ok(None);
+        }
+        let job: Job = serde_json::from_str(&fs::read_to_string(file_path).await?)?;
+        Ok((job.user_login() == user_login).then_some(job))
+    }
+
+    async fn list(&self, user_login: &str) -> anyhow::Result<Vec<Job>> {
+        let mut jobs = self
+            .load_all()
+            .await?
+            .into_iter()
+            .filter(|x| x.user_login() == user_login)
+            .collect::<Vec<_>>();
+        jobs.reverse();
+        Ok(jobs)
+    }
+
+    async fn list_unfinished(&self) -> anyhow::Result<Vec<Job>> {
+        Ok(self
+            .load_all()
+            .await?
+            .into_iter()
+            .filter(|x| !x.status().is_finished())
+            .collect())
+    }
+
+    async fn delete_finished_before(&self, before: DateTime<Utc>) -> anyhow::Result<usize> {
+        let mut removed = 0;
+        for job in self.load_all().await? {
+            if job.status().is_finished() && job.updated_at() < before {
+                fs::remove_file(self.get_job_path(job.id())).await?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[derive(Clone)]
+pub struct SqliteJobRepository {
+    storage: SqliteStorage,
+}
+
+impl SqliteJobRepository {
+    pub async fn new(storage: SqliteStorage) -> anyhow::Result<Self> {
+        storage
+            .call(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS jobs (
+                        id TEXT PRIMARY KEY,
+                        user_login TEXT NOT NULL,
+                        finished INTEGER NOT NULL,
+                        updated_at TEXT NOT NULL,
+                        data TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS jobs_user ON jobs (user_login, id);
+                    CREATE INDEX IF NOT EXISTS jobs_finished ON jobs (finished, updated_at);",
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(Self { storage })
+    }
+
+    async fn query(&self, sql: &'static str, param: Option<String>) -> anyhow::Result<Vec<Job>> {
+        let rows = self
+            .storage
+            .call(move |conn| {
+                let mut statement = conn.prepare(sql)?;
+                let rows = match param {
+                    Some(param) => statement
+                        .query_map(params![param], |row| row.get::<_, String>(0))?
+                        .collect::<Result<Vec<_>, _>>()?,
+                    None => statement
+                        .query_map([], |row| row.get::<_, String>(0))?
+                        .collect::<Result<Vec<_>, _>>()?,
+                };
+                Ok(rows)
+            })
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+#[async_trait]
+impl JobRepository for SqliteJobRepository {
+    async fn save(&self, job: &Job) -> anyhow::Result<()> {
+        let id = job.id().to_owned();
+        let user_login = job.user_login().to_owned();
+        let finished = job.status().is_finished();
+        let updated_at = format_timestamp(job.updated_at());
+        let json = serde_json::to_string(job)?;
+
+        self.storage
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO jobs (id, user_login, finished, updated_at, data)
+                     VALUES (?1, ?2, ?3, ?4, ?5)
+                     ON CONFLICT(id) DO UPDATE SET
+                        finished = excluded.finished,
+                        updated_at = excluded.updated_at,
+                        data = excluded.data",
+                    params![id, user_login, finished, updated_at, json],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get(&self, user_login: &str, id: &str) -> anyhow::Result<Option<Job>> {
+        let user_login = user_login.to_owned();
+        let id = id.to_owned();
+        let json = self
+            .storage
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT data FROM jobs WHERE user_login = ?1 AND id = ?2",
+                        params![user_login, id],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .optional()?)
+            })
+            .await?;
+
+        json.map(|x| serde_json::from_str(&x).map_err(Into::into))
+            .transpose()
+    }
+
+    async fn list(&self, user_login: &str) -> anyhow::Result<Vec<Job>> {
+        self.query(
+            "SELECT data FROM jobs WHERE user_login = ?1 ORDER BY id DESC",
+            Some(user_login.to_owned()),
+        )
+        .await
+    }
+
+    async fn list_unfinished(&self) -> anyhow::Result<Vec<Job>> {
+        self.query("SELECT data FROM jobs WHERE finished = 0 ORDER BY id", None)
+            .await
+    }
+
+    async fn delete_finished_before(&self, before: DateTime<Utc>) -> anyhow::Result<usize> {
+        let before = format_timestamp(before);
+        self.storage
+            .call(move |conn| {
+                Ok(conn.execute(
+                    "DELETE FROM jobs WHERE finished = 1 AND updated_at < ?1",
+                    params![before],
+                )?)
+            })
+            .await
+    }
+}
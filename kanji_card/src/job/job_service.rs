@@ -0,0 +1,280 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tokio::sync::{Mutex, broadcast, mpsc};
+use tracing::{error, info, instrument, warn};
+use ulid::Ulid;
+
+use crate::{
+    config::JobsConfig,
+    job::{
+        domain::{Job, JobEvent, JobEventKind, JobKind, JobResult, JobStatus},
+        job_repository::JobRepository,
+    },
+    llm::ExtractedWord,
+    rule::rule_service::RuleService,
+    usage::usage_service::QuotaExceeded,
+    word::set_service::SetService,
+};
+
+/// Queue entry: owner and id of a job stored in the repository.
+type QueuedJob = (String, String);
+
+/// Events kept for slow subscribers before they start lagging.
+const EVENT_BUFFER: usize = 1024;
+
+/// Persistent queue of LLM jobs worked off by a fixed pool of tasks. The
+/// repository is the source of truth; the channel only wakes the workers,
+/// so unfinished jobs are simply queued again after a restart.
+pub struct JobService {
+    repository: Arc<dyn JobRepository>,
+    set_service: Arc<SetService>,
+    rule_service: Arc<RuleService>,
+    config: JobsConfig,
+    queue: mpsc::UnboundedSender<QueuedJob>,
+    events: broadcast::Sender<JobEvent>,
+}
+
+impl JobService {
+    /// Spawns the workers and re-queues jobs left over from the last run.
+    pub async fn start(
+        repository: Arc<dyn JobRepository>,
+        set_service: Arc<SetService>,
+        rule_service: Arc<RuleService>,
+        config: JobsConfig,
+    ) -> Result<Arc<Self>> {
+        let (queue, receiver) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let service = Arc::new(Self {
+            repository,
+            set_service,
+            rule_service,
+            config,
+            queue,
+            events,
+        });
+
+        let receiver = Arc::new(Mutex::new(receiver));
+        for worker in 0..service.config.workers.max(1) {
+            let service = service.clone();
+            let receiver = receiver.clone();
+            tokio::spawn(async move {
+                loop {
+                    let Some((user_login, id)) = receiver.lock().await.recv().await else {
+                        break;
+                    };
+                    service.run(worker, &user_login, &id).await;
+                }
+            });
+        }
+
+        service.recover().await?;
+        Ok(service)
+    }
+
+    async fn recover(self: &Arc<Self>) -> Result<()> {
+        let before = Utc::now() - Duration::days(self.config.retention_days as i64);
+        let removed = self.repository.delete_finished_before(before).await?;
+        if removed > 0 {
+            info!("Removed {} finished jobs older than {}", removed, before);
+        }
+
+        let jobs = self.repository.list_unfinished().await?;
+        info!("Resuming {} unfinished jobs", jobs.len());
+        for mut job in jobs {
+            if job.status() == JobStatus::Running {
+                job.requeue(Some("Interrupted by a server restart".to_owned()), None);
+                self.repository.save(&job).await?;
+            }
+            self.schedule(&job);
+        }
+        Ok(())
+    }
+
+    fn schedule(self: &Arc<Self>, job: &Job) {
+        let entry = (job.user_login().to_owned(), job.id().to_owned());
+        let delay = job
+            .run_after()
+            .and_then(|x| (x - Utc::now()).to_std().ok())
+            .filter(|x| !x.is_zero());
+        match delay {
+            None => {
+                let _ = self.queue.send(entry);
+            }
+            Some(delay) => {
+                let queue = self.queue.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    let _ = queue.send(entry);
+                });
+            }
+        }
+    }
+
+    #[instrument(skip(self, kind), fields(kind = kind.name()))]
+    pub async fn enqueue(self: &Arc<Self>, user_login: &str, kind: JobKind) -> Result<Job> {
+        let job = Job::new(user_login, kind);
+        self.repository.save(&job).await?;
+        info!("Queued job {} for {}", job.id(), user_login);
+        self.publish(JobEvent::status(&job));
+        self.schedule(&job);
+        Ok(job)
+    }
+
+    /// Events of all users' jobs; subscribers filter by owner.
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.events.subscribe()
+    }
+
+    fn publish(&self, event: JobEvent) {
+        // Sending only fails when nobody is listening.
+        let _ = self.events.send(event);
+    }
+
+    /// The job, or `None` when the user has no job with this id.
+    pub async fn get(&self, user_login: &str, id: &str) -> Result<Option<Job>> {
+        // Ids end up in file names, so anything but a ULID is unknown.
+        if Ulid::from_string(id).is_err() {
+            return Ok(None);
+        }
+        self.repository.get(user_login, id).await
+    }
+
+    pub async fn list(&self, user_login: &str) -> Result<Vec<Job>> {
+        self.repository.list(user_login).await
+    }
+
+    #[instrument(skip(self))]
+    async fn run(self: &Arc<Self>, worker: usize, user_login: &str, id: &str) {
+        let mut job = match self.repository.get(user_login, id).await {
+            Ok(Some(job)) if job.status() == JobStatus::Queued => job,
+            Ok(_) => return,
+            Err(e) => {
+                error!("Failed to load job {}: {}", id, e);
+                return;
+            }
+        };
+
+        job.start();
+        if let Err(e) = self.repository.save(&job).await {
+            error!("Failed to mark job {} as running: {}", id, e);
+            return;
+        }
+        self.publish(JobEvent::status(&job));
+        info!(
+            "Worker {} runs job {} ({}), attempt {}",
+            worker,
+            id,
+            job.kind().name(),
+            job.attempts()
+        );
+
+        match self.execute(&job).await {
+            Ok(result) => {
+                info!("Job {} succeeded", id);
+                job.succeed(result);
+            }
+            Err(e) if e.is::<QuotaExceeded>() || job.attempts() >= self.config.max_attempts => {
+                error!("Job {} failed: {}", id, e);
+                job.fail(e.to_string());
+            }
+            Err(e) => {
+                let delay = self.config.retry_delay_secs * job.attempts() as u64;
+                warn!("Job {} failed, retrying in {}s: {}", id, delay, e);
+                job.requeue(
+                    Some(e.to_string()),
+                    Some(Utc::now() + Duration::seconds(delay as i64)),
+                );
+            }
+        }
+
+        if let Err(e) = self.repository.save(&job).await {
+            error!("Failed to save job {}: {}", id, e);
+            return;
+        }
+        match (job.status(), job.result(), job.error()) {
+            (JobStatus::Succeeded, Some(result), _) => self.publish(JobEvent::new(
+                &job,
+                JobEventKind::Succeeded {
+                    result: result.clone(),
+                },
+            )),
+            (JobStatus::Failed, _, error) => self.publish(JobEvent::new(
+                &job,
+                JobEventKind::Failed {
+                    error: error.unwrap_or_default().to_owned(),
+                },
+            )),
+            _ => {
+                self.publish(JobEvent::status(&job));
+                self.schedule(&job);
+            }
+        }
+    }
+
+    async fn execute(&self, job: &Job) -> Result<JobResult> {
+        let user_login = job.user_login();
+        let progress = |words: &[ExtractedWord]| {
+            self.publish(JobEvent::new(
+                job,
+                JobEventKind::Words {
+                    words: words.to_vec(),
+                },
+            ))
+        };
+        Ok(match job.kind() {
+            JobKind::ExtractWordsFromText {
+                text,
+                bypass_cache,
+                skip_known,
+            } => {
+                let extraction = self
+                    .set_service
+                    .extract_words_from_text(
+                        user_login,
+                        text.clone(),
+                        *bypass_cache,
+                        *skip_known,
+                        Some(&progress),
+                    )
+                    .await?;
+                JobResult::Words {
+                    words: extraction.words,
+                    known_words: extraction.known_words,
+                }
+            }
+            JobKind::ExtractWordsFromImage {
+                image_data,
+                bypass_cache,
+            } => JobResult::Words {
+                words: self
+                    .set_service
+                    .extract_words_from_image(
+                        user_login,
+                        image_data.clone(),
+                        *bypass_cache,
+                        Some(&progress),
+                    )
+                    .await?,
+                known_words: vec![],
+            },
+            JobKind::CreateRuleFromText { text } => {
+                let rule = self.rule_service.create_from_text(user_login, text).await?;
+                JobResult::Rule {
+                    rule_id: rule.id().to_owned(),
+                    title: rule.title().to_owned(),
+                }
+            }
+            JobKind::CreateRuleFromDescription { description } => {
+                let rule = self
+                    .rule_service
+                    .create_from_description(user_login, description)
+                    .await?;
+                JobResult::Rule {
+                    rule_id: rule.id().to_owned(),
+                    title: rule.title().to_owned(),
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,4 @@
+pub mod api;
+pub mod domain;
+pub mod job_repository;
+pub mod job_service;
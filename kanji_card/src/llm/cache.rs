@@ -0,0 +1,138 @@
+//! Persistent cache of LLM answers, one file per request, keyed by the hash
+//! of the model, the rendered prompt and the image bytes. Only answers that
+//! parsed successfully are stored.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt::Write, path::PathBuf, time::SystemTime};
+use tokio::{fs, sync::Mutex};
+use tracing::{info, warn};
+
+use crate::{config::LlmCacheConfig, llm::LlmRequest};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: DateTime<Utc>,
+    content: String,
+}
+
+pub struct LlmCache {
+    config: LlmCacheConfig,
+    /// Serializes writes so concurrent evictions do not race.
+    write_lock: Mutex<()>,
+}
+
+impl LlmCache {
+    pub fn new(config: LlmCacheConfig) -> Self {
+        info!(
+            "LLM cache at {} with ttl {}s and limit {} bytes",
+            config.dir, config.ttl_secs, config.max_bytes
+        );
+        Self {
+            config,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn key(model: &str, request: &LlmRequest) -> String {
+        let mut hasher = Sha256::new();
+        for part in [
+            model.as_bytes(),
+            request.prompt.as_bytes(),
+            request.image.as_deref().unwrap_or_default(),
+            request
+                .schema
+                .as_ref()
+                .map(|x| x.name.as_bytes())
+                .unwrap_or_default(),
+        ] {
+            // Length prefixes keep ("ab", "c") and ("a", "bc") apart.
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .fold(String::with_capacity(64), |mut key, byte| {
+                let _ = write!(key, "{byte:02x}");
+                key
+            })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        PathBuf::from(&self.config.dir).join(format!("{key}.json"))
+    }
+
+    /// Cached answer for `key`, unless it is missing, unreadable or expired.
+    pub async fn get(&self, key: &str) -> Option<String> {
+        let path = self.path(key);
+        let content = fs::read(&path).await.ok()?;
+        let entry = match serde_json::from_slice::<CacheEntry>(&content) {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Dropping unreadable LLM cache entry {}: {}", key, e);
+                let _ = fs::remove_file(&path).await;
+                return None;
+            }
+        };
+
+        if Utc::now() - entry.created_at > Duration::seconds(self.config.ttl_secs as i64) {
+            info!("LLM cache entry {} expired", key);
+            let _ = fs::remove_file(&path).await;
+            return None;
+        }
+        Some(entry.content)
+    }
+
+    /// Stores an answer; failures only cost a future cache miss.
+    pub async fn put(&self, key: &str, content: &str) {
+        if let Err(e) = self.try_put(key, content).await {
+            warn!("Failed to write LLM cache entry {}: {}", key, e);
+        }
+    }
+
+    async fn try_put(&self, key: &str, content: &str) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        fs::create_dir_all(&self.config.dir).await?;
+        let entry = CacheEntry {
+            created_at: Utc::now(),
+            content: content.to_owned(),
+        };
+        fs::write(self.path(key), serde_json::to_vec(&entry)?).await?;
+        self.evict().await
+    }
+
+    /// Removes the oldest entries until the cache fits into `max_bytes`.
+    async fn evict(&self) -> Result<()> {
+        let mut entries = Vec::new();
+        let mut total = 0;
+        let mut dir = fs::read_dir(&self.config.dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+            total += metadata.len();
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((modified, metadata.len(), entry.path()));
+        }
+        if total <= self.config.max_bytes {
+            return Ok(());
+        }
+
+        entries.sort_by_key(|x| x.0);
+        let mut removed = 0;
+        for (_, len, path) in entries {
+            if total <= self.config.max_bytes {
+                break;
+            }
+            fs::remove_file(&path).await?;
+            total -= len;
+            removed += 1;
+        }
+        info!("Evicted {} LLM cache entries", removed);
+        Ok(())
+    }
+}
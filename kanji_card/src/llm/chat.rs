@@ -0,0 +1,244 @@
+//! Wire format of the OpenAI `/chat/completions` endpoint, spoken by
+//! OpenRouter as well as llama.cpp, Ollama and other local servers.
+
+use anyhow::{Result, anyhow};
+use base64::{Engine, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use reqwest::StatusCode;
+
+use crate::{
+    llm::{
+        LlmRequest, LlmResponse,
+        resilience::{TransientError, parse_retry_after},
+        schema::ResponseSchema,
+    },
+    usage::record::TokenUsage,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ChatRequest {
+    pub model: String,
+    pub messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// OpenRouter only: asks for the charged cost in the `usage` block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UsageAccounting>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageAccounting {
+    pub include: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseFormat {
+    #[serde(rename = "type")]
+    format_type: String,
+    json_schema: JsonSchemaFormat,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSchemaFormat {
+    name: String,
+    strict: bool,
+    schema: serde_json::Value,
+}
+
+impl ResponseFormat {
+    pub fn json_schema(schema: &ResponseSchema) -> Self {
+        Self {
+            format_type: "json_schema".to_string(),
+            json_schema: JsonSchemaFormat {
+                name: schema.name.clone(),
+                strict: true,
+                schema: schema.schema.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Message {
+    pub role: String,
+    pub content: Vec<Content>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Content {
+    Text {
+        #[serde(rename = "type")]
+        content_type: String,
+        text: String,
+    },
+    Image {
+        #[serde(rename = "type")]
+        content_type: String,
+        image_url: ImageUrl,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageUrl {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<ResponseUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    #[serde(default)]
+    cost: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+/// Single user message carrying the prompt and the optional image.
+pub fn user_messages(request: &LlmRequest) -> Vec<Message> {
+    let mut content = vec![Content::Text {
+        content_type: "text".to_string(),
+        text: request.prompt.clone(),
+    }];
+
+    if let Some(image) = &request.image {
+        let image_base64 = general_purpose::STANDARD.encode(image);
+        content.push(Content::Image {
+            content_type: "image_url".to_string(),
+            image_url: ImageUrl {
+                url: format!("data:image/jpeg;base64,{image_base64}"),
+            },
+        });
+    }
+
+    vec![Message {
+        role: "user".to_string(),
+        content,
+    }]
+}
+
+/// `response_format` for the request, unless the backend is configured
+/// without structured output support.
+pub fn response_format(request: &LlmRequest, structured_output: bool) -> Option<ResponseFormat> {
+    request
+        .schema
+        .as_ref()
+        .filter(|_| structured_output)
+        .map(ResponseFormat::json_schema)
+}
+
+/// Posts a chat completion and returns the first choice's content.
+/// `backend` only names the server in logs and errors.
+pub async fn complete(
+    client: &reqwest::Client,
+    backend: &str,
+    base_url: &str,
+    api_key: Option<&str>,
+    request: &ChatRequest,
+) -> Result<LlmResponse> {
+    let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+    info!("Sending request to {} at {}", backend, url);
+
+    let mut builder = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(request);
+    if let Some(api_key) = api_key {
+        builder = builder.header("Authorization", format!("Bearer {api_key}"));
+    }
+
+    let response = match builder.send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            error!(
+                error = %e,
+                error_type = ?e.status(),
+                "Failed to send request to {}", backend
+            );
+            return Err(TransientError {
+                message: format!("Failed to send request to {}: {}", backend, e),
+                retry_after: None,
+            }
+            .into());
+        }
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|x| x.to_str().ok())
+            .and_then(parse_retry_after);
+        let error_text = response.text().await.unwrap_or_default();
+        error!(
+            status = %status,
+            error_text = %error_text,
+            "{} returned error response", backend
+        );
+        let message = format!("{} error {}: {}", backend, status, error_text);
+        if status == StatusCode::TOO_MANY_REQUESTS
+            || status == StatusCode::REQUEST_TIMEOUT
+            || status.is_server_error()
+        {
+            return Err(TransientError {
+                message,
+                retry_after,
+            }
+            .into());
+        }
+        return Err(anyhow!(message));
+    }
+
+    let chat_response: ChatResponse = match response.json().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            error!(error = %e, "Failed to parse {} response as JSON", backend);
+            return Err(anyhow!("Failed to parse {} response: {}", backend, e));
+        }
+    };
+
+    let usage = chat_response.usage.map(|x| TokenUsage {
+        prompt_tokens: x.prompt_tokens,
+        completion_tokens: x.completion_tokens,
+        cost: x.cost,
+    });
+    let content = chat_response
+        .choices
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            error!("No choices in {} response", backend);
+            anyhow!("No choices in response")
+        })?
+        .message
+        .content;
+
+    info!("Successfully received response from {}", backend);
+    Ok(LlmResponse { content, usage })
+}
@@ -0,0 +1,69 @@
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use serde::Deserialize;
+use tracing::{info, instrument, warn};
+
+use crate::llm::{LlmProvider, LlmRequest, LlmResponse, ModelKind};
+
+/// Canned answer returned when the prompt contains `contains`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fixture {
+    pub contains: String,
+    /// Restricts the fixture to one model kind; any kind matches when unset.
+    #[serde(default)]
+    pub kind: Option<ModelKind>,
+    /// Returned verbatim when a string, serialized as JSON otherwise.
+    pub response: serde_json::Value,
+}
+
+/// Deterministic provider for development and tests: answers from fixtures
+/// in file order, without any network access.
+pub struct MockProvider {
+    fixtures: Vec<Fixture>,
+}
+
+impl MockProvider {
+    pub fn new(fixtures: Vec<Fixture>) -> Self {
+        Self { fixtures }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read LLM fixtures from {}", path))?;
+        let fixtures: Vec<Fixture> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse LLM fixtures from {}", path))?;
+        info!("Loaded {} LLM fixtures from {}", fixtures.len(), path);
+        Ok(Self::new(fixtures))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    #[instrument(skip(self, request), fields(kind = ?request.kind))]
+    async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse> {
+        let fixture = self
+            .fixtures
+            .iter()
+            .find(|x| {
+                x.kind.is_none_or(|kind| kind == request.kind)
+                    && request.prompt.contains(&x.contains)
+            })
+            .ok_or_else(|| {
+                warn!("No LLM fixture matches the {:?} prompt", request.kind);
+                anyhow!("No LLM fixture matches the prompt")
+            })?;
+
+        let content = match &fixture.response {
+            serde_json::Value::String(text) => text.clone(),
+            value => value.to_string(),
+        };
+        Ok(LlmResponse {
+            content,
+            usage: None,
+        })
+    }
+
+    fn model(&self, _kind: ModelKind) -> String {
+        "mock".to_owned()
+    }
+}
@@ -0,0 +1,341 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, instrument, warn};
+use utoipa::ToSchema;
+
+use crate::{
+    config::{LlmProviderKind, Settings},
+    rule::rule::JapanesePartOfSpeech,
+    usage::{
+        record::{TokenUsage, UsageEndpoint},
+        usage_service::UsageService,
+    },
+};
+
+pub mod cache;
+pub mod chat;
+pub mod mock;
+pub mod openai_compatible;
+pub mod openrouter;
+pub mod resilience;
+pub mod schema;
+
+use cache::LlmCache;
+use schema::ResponseSchema;
+
+// Public types that will be used by services
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, ToSchema, Clone, Hash)]
+pub struct ExtractedWord {
+    pub word: String,
+    pub translation: String,
+    #[serde(default)]
+    pub reading: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WordsResponse {
+    #[schema(inline)]
+    pub words: Vec<ExtractedWord>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GrammarRuleResponse {
+    pub title: String,
+    pub conspect: String,
+    #[schema(inline)]
+    pub part_of_speech: JapanesePartOfSpeech,
+    #[schema(inline)]
+    pub examples: Vec<GrammarExampleResponse>,
+    #[schema(inline)]
+    pub tests: Vec<GrammarTestResponse>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GrammarExampleResponse {
+    pub title: String,
+    pub content: String,
+    pub description: String,
+    pub content_translation: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GrammarTestResponse {
+    pub rus_description: String,
+    pub question_content: String,
+    pub answer: String,
+}
+
+/// Which of the configured models a request is meant for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelKind {
+    Text,
+    Image,
+    Reasoning,
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    pub kind: ModelKind,
+    pub prompt: String,
+    pub image: Option<Vec<u8>>,
+    /// Reasoning requests leave this to the model.
+    pub temperature: Option<f32>,
+    pub schema: Option<ResponseSchema>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LlmResponse {
+    pub content: String,
+    /// Tokens billed for the call, when the backend reports them.
+    pub usage: Option<TokenUsage>,
+}
+
+/// A backend that turns a prompt into the raw text of the model's answer.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse>;
+
+    /// Identifies the model answering `kind` requests, for cache keys.
+    fn model(&self, kind: ModelKind) -> String;
+}
+
+/// Appended to the original prompt when an answer does not parse.
+const REPAIR_PROMPT: &str = "\n\nYour previous answer was not valid JSON for the requested schema.\nParse error: {error}\nPrevious answer:\n{answer}\n\nReply again with only the corrected JSON, without any commentary.";
+
+/// Typed front for the configured [`LlmProvider`]: sends prompts and parses
+/// the JSON the prompts ask for.
+#[derive(Clone)]
+pub struct LlmService {
+    provider: Arc<dyn LlmProvider>,
+    repair_attempts: u32,
+    /// Extraction (text and image) answers only; reasoning requests generate
+    /// rules, where asking again should give a fresh answer.
+    cache: Option<Arc<LlmCache>>,
+    usage_service: Option<Arc<UsageService>>,
+    /// User and feature that calls are checked against and billed to.
+    scope: Option<(String, UsageEndpoint)>,
+}
+
+impl LlmService {
+    pub fn new(provider: Arc<dyn LlmProvider>, repair_attempts: u32) -> Self {
+        Self {
+            provider,
+            repair_attempts,
+            cache: None,
+            usage_service: None,
+            scope: None,
+        }
+    }
+
+    pub fn with_usage(mut self, usage_service: Arc<UsageService>) -> Self {
+        self.usage_service = Some(usage_service);
+        self
+    }
+
+    /// Same service, enforcing the quota of `user_login` and accounting the
+    /// tokens to `endpoint`.
+    pub fn for_user(&self, user_login: &str, endpoint: UsageEndpoint) -> Self {
+        Self {
+            scope: Some((user_login.to_owned(), endpoint)),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_cache(mut self, cache: LlmCache) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Same service, always asking the model.
+    pub fn without_cache(&self) -> Self {
+        Self {
+            cache: None,
+            ..self.clone()
+        }
+    }
+
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let provider: Arc<dyn LlmProvider> = match settings.llm.provider {
+            LlmProviderKind::OpenRouter => Arc::new(resilience::ResilientProvider::new(
+                Arc::new(openrouter::OpenRouterProvider::new(
+                    settings.openrouter.clone(),
+                )?),
+                settings.openrouter.retry.clone(),
+                settings.openrouter.circuit_breaker.clone(),
+            )),
+            LlmProviderKind::OpenAiCompatible => Arc::new(
+                openai_compatible::OpenAiCompatibleProvider::new(settings.llm.local.clone())?,
+            ),
+            LlmProviderKind::Mock => Arc::new(mock::MockProvider::from_file(
+                &settings.llm.mock.fixtures_path,
+            )?),
+        };
+        let service = Self::new(provider, settings.llm.repair_attempts);
+        Ok(if settings.llm.cache.enabled {
+            service.with_cache(LlmCache::new(settings.llm.cache.clone()))
+        } else {
+            service
+        })
+    }
+
+    #[instrument(skip(self, prompt, image_data))]
+    pub async fn send_image_request<T>(
+        &self,
+        prompt: &str,
+        image_data: &[u8],
+        temperature: f32,
+    ) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de> + ToSchema,
+    {
+        self.invoke(LlmRequest {
+            kind: ModelKind::Image,
+            prompt: prompt.to_owned(),
+            image: Some(image_data.to_vec()),
+            temperature: Some(temperature),
+            schema: Some(ResponseSchema::of::<T>()),
+        })
+        .await
+    }
+
+    #[instrument(skip(self, prompt))]
+    pub async fn send_request<T>(&self, prompt: &str, temperature: f32) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de> + ToSchema,
+    {
+        self.invoke(LlmRequest {
+            kind: ModelKind::Text,
+            prompt: prompt.to_owned(),
+            image: None,
+            temperature: Some(temperature),
+            schema: Some(ResponseSchema::of::<T>()),
+        })
+        .await
+    }
+
+    #[instrument(skip(self, prompt))]
+    pub async fn send_reasoning_request<T>(&self, prompt: &str) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de> + ToSchema,
+    {
+        self.invoke(LlmRequest {
+            kind: ModelKind::Reasoning,
+            prompt: prompt.to_owned(),
+            image: None,
+            temperature: None,
+            schema: Some(ResponseSchema::of::<T>()),
+        })
+        .await
+    }
+
+    /// Sends the request and parses the answer, re-prompting with the parse
+    /// error up to `repair_attempts` times when the model returns malformed
+    /// JSON.
+    async fn invoke<T>(&self, request: LlmRequest) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de> + ToSchema,
+    {
+        let cache = self
+            .cache
+            .as_ref()
+            .filter(|_| request.kind != ModelKind::Reasoning)
+            .map(|x| {
+                (
+                    x,
+                    LlmCache::key(&self.provider.model(request.kind), &request),
+                )
+            });
+        if let Some((cache, key)) = &cache
+            && let Some(content) = cache.get(key).await
+            && let Ok(parsed_response) = serde_json::from_str::<T>(&content)
+        {
+            info!("Using cached {:?} response {}", request.kind, key);
+            return Ok(parsed_response);
+        }
+
+        let usage = self.usage_service.as_ref().zip(self.scope.as_ref());
+        if let Some((usage_service, (user_login, _))) = usage {
+            usage_service.check(user_login).await?;
+        }
+
+        let model = self.provider.model(request.kind);
+        let mut attempt = request.clone();
+        let mut repairs = 0;
+        loop {
+            let response = self.provider.complete(&attempt).await?;
+            if let Some((usage_service, (user_login, endpoint))) = usage
+                && let Err(e) = usage_service
+                    .record(
+                        user_login,
+                        *endpoint,
+                        model.clone(),
+                        response.usage.unwrap_or_else(|| {
+                            TokenUsage::estimate(
+                                &attempt.prompt,
+                                attempt.image.is_some(),
+                                &response.content,
+                            )
+                        }),
+                    )
+                    .await
+            {
+                error!("Failed to record LLM usage of {}: {}", user_login, e);
+            }
+            let content = strip_fences(&response.content);
+
+            let error = match serde_json::from_str::<T>(content) {
+                Ok(parsed_response) => {
+                    info!(
+                        "Successfully parsed {:?} response after {} repairs",
+                        request.kind, repairs
+                    );
+                    if let Some((cache, key)) = &cache {
+                        cache.put(key, content).await;
+                    }
+                    return Ok(parsed_response);
+                }
+                Err(e) => e,
+            };
+
+            if repairs >= self.repair_attempts {
+                error!(
+                    error = %error,
+                    content = %content,
+                    kind = ?request.kind,
+                    repairs,
+                    "Failed to parse LLM response as JSON"
+                );
+                return Err(anyhow!(
+                    "Failed to parse LLM response as JSON: {}. Content: {}",
+                    error,
+                    content
+                ));
+            }
+
+            repairs += 1;
+            warn!(
+                error = %error,
+                kind = ?request.kind,
+                "LLM response is not valid JSON, asking for repair {}/{}",
+                repairs,
+                self.repair_attempts
+            );
+            attempt.prompt = request.prompt.clone()
+                + &REPAIR_PROMPT
+                    .replace("{error}", &error.to_string())
+                    .replace("{answer}", content);
+        }
+    }
+}
+
+fn strip_fences(content: &str) -> &str {
+    content
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim()
+}
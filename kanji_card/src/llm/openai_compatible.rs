@@ -0,0 +1,75 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+use tracing::{info, instrument};
+
+use crate::{
+    config::LocalLlmConfig,
+    llm::{
+        LlmProvider, LlmRequest, LlmResponse, ModelKind,
+        chat::{self, ChatRequest},
+    },
+};
+
+const BACKEND: &str = "local LLM server";
+
+/// Any server implementing the OpenAI chat API, such as llama.cpp's
+/// `llama-server` or Ollama.
+pub struct OpenAiCompatibleProvider {
+    client: reqwest::Client,
+    config: LocalLlmConfig,
+}
+
+impl OpenAiCompatibleProvider {
+    fn model_name(&self, kind: ModelKind) -> &String {
+        match kind {
+            ModelKind::Text => &self.config.text_model,
+            ModelKind::Image => &self.config.image_model,
+            ModelKind::Reasoning => &self.config.reasoning_model,
+        }
+    }
+
+    pub fn new(config: LocalLlmConfig) -> Result<Self> {
+        info!(
+            "Initializing OpenAI-compatible provider at {} with text model: {}, image model: {}, reasoning model: {}",
+            config.base_url, config.text_model, config.image_model, config.reasoning_model
+        );
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()?;
+        Ok(Self { client, config })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    #[instrument(skip(self, request), fields(kind = ?request.kind))]
+    async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse> {
+        let model = self.model_name(request.kind);
+
+        // Local servers tend to know only `max_tokens`, for every model.
+        let chat_request = ChatRequest {
+            model: model.clone(),
+            messages: chat::user_messages(request),
+            max_tokens: Some(self.config.max_tokens),
+            max_completion_tokens: None,
+            temperature: request.temperature,
+            response_format: chat::response_format(request, self.config.structured_output),
+            usage: None,
+        };
+
+        let api_key = Some(self.config.api_key.as_str()).filter(|x| !x.is_empty());
+        chat::complete(
+            &self.client,
+            BACKEND,
+            &self.config.base_url,
+            api_key,
+            &chat_request,
+        )
+        .await
+    }
+
+    fn model(&self, kind: ModelKind) -> String {
+        format!("{}:{}", self.config.base_url, self.model_name(kind))
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::time::Duration;
+use tracing::{info, instrument};
+
+use crate::{
+    config::OpenRouterConfig,
+    llm::{
+        LlmProvider, LlmRequest, LlmResponse, ModelKind,
+        chat::{self, ChatRequest, UsageAccounting},
+    },
+};
+
+const BACKEND: &str = "OpenRouter API";
+
+pub struct OpenRouterProvider {
+    client: reqwest::Client,
+    config: OpenRouterConfig,
+}
+
+impl OpenRouterProvider {
+    pub fn new(config: OpenRouterConfig) -> Result<Self> {
+        info!(
+            "Initializing OpenRouter provider with text model: {}, image model: {}, reasoning model: {}",
+            config.text_model, config.image_model, config.reasoning_model
+        );
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()?;
+        Ok(Self { client, config })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenRouterProvider {
+    #[instrument(skip(self, request), fields(kind = ?request.kind))]
+    async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse> {
+        let messages = chat::user_messages(request);
+        let response_format = chat::response_format(request, self.config.structured_output);
+        // Reasoning models only accept `max_completion_tokens`.
+        let chat_request = match request.kind {
+            ModelKind::Reasoning => ChatRequest {
+                model: self.config.reasoning_model.clone(),
+                messages,
+                max_tokens: None,
+                max_completion_tokens: Some(self.config.max_completion_tokens),
+                temperature: None,
+                response_format,
+                usage: Some(UsageAccounting { include: true }),
+            },
+            ModelKind::Text | ModelKind::Image => ChatRequest {
+                model: match request.kind {
+                    ModelKind::Image => self.config.image_model.clone(),
+                    _ => self.config.text_model.clone(),
+                },
+                messages,
+                max_tokens: Some(self.config.max_completion_tokens),
+                max_completion_tokens: None,
+                temperature: request.temperature,
+                response_format,
+                usage: Some(UsageAccounting { include: true }),
+            },
+        };
+
+        chat::complete(
+            &self.client,
+            BACKEND,
+            &self.config.base_url,
+            Some(&self.config.api_key),
+            &chat_request,
+        )
+        .await
+    }
+
+    fn model(&self, kind: ModelKind) -> String {
+        let model = match kind {
+            ModelKind::Text => &self.config.text_model,
+            ModelKind::Image => &self.config.image_model,
+            ModelKind::Reasoning => &self.config.reasoning_model,
+        };
+        format!("openrouter:{model}")
+    }
+}
@@ -0,0 +1,426 @@
+//! Retries with exponential backoff and a circuit breaker around an
+//! [`LlmProvider`], so a transient 429/5xx does not fail the user's request
+//! and a provider outage fails fast instead of piling up slow requests.
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use rand::Rng;
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    config::{CircuitBreakerConfig, RetryConfig},
+    llm::{LlmProvider, LlmRequest, LlmResponse, ModelKind},
+};
+
+/// Failure worth retrying: network errors, timeouts, 408, 429 and 5xx.
+#[derive(Debug)]
+pub struct TransientError {
+    pub message: String,
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for TransientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TransientError {}
+
+/// Parses a `Retry-After` header given either in seconds or as an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    httpdate::parse_http_date(value)
+        .ok()
+        .map(|x| x.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// Set while the single probe request after the cooldown is running.
+    probing: bool,
+}
+
+struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.config.failure_threshold > 0
+    }
+
+    fn acquire(&self) -> Result<Permit<'_>> {
+        let permit = Permit {
+            breaker: self,
+            probe: false,
+        };
+        if !self.enabled() {
+            return Ok(permit);
+        }
+        let mut state = self.state.lock().unwrap_or_else(|x| x.into_inner());
+        let Some(open_until) = state.open_until else {
+            return Ok(permit);
+        };
+
+        let now = Instant::now();
+        if now < open_until || state.probing {
+            let wait = open_until.saturating_duration_since(now).as_secs().max(1);
+            return Err(anyhow!(
+                "LLM provider is unavailable after {} consecutive failures, try again in {}s",
+                state.consecutive_failures,
+                wait
+            ));
+        }
+
+        info!("Circuit breaker cooldown is over, probing the LLM provider");
+        state.probing = true;
+        Ok(Permit {
+            probe: true,
+            ..permit
+        })
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|x| x.into_inner());
+        if state.open_until.is_some() {
+            info!("LLM provider recovered, closing circuit breaker");
+        }
+        *state = BreakerState::default();
+    }
+
+    fn record_failure(&self) {
+        if !self.enabled() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|x| x.into_inner());
+        state.consecutive_failures += 1;
+        if state.probing || state.consecutive_failures >= self.config.failure_threshold {
+            error!(
+                "Opening circuit breaker for {}s after {} consecutive LLM failures",
+                self.config.open_secs, state.consecutive_failures
+            );
+            state.open_until = Some(Instant::now() + Duration::from_secs(self.config.open_secs));
+            state.probing = false;
+        }
+    }
+}
+
+/// Permission to call the provider. A probe that is dropped before its
+/// outcome is recorded, e.g. when the caller's future is cancelled, lets the
+/// next call probe instead of keeping the breaker open for good.
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+}
+
+impl Permit<'_> {
+    fn record_success(mut self) {
+        self.probe = false;
+        self.breaker.record_success();
+    }
+
+    fn record_failure(mut self) {
+        self.probe = false;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            warn!("LLM probe was cancelled before it finished");
+            let mut state = self.breaker.state.lock().unwrap_or_else(|x| x.into_inner());
+            state.probing = false;
+        }
+    }
+}
+
+pub struct ResilientProvider {
+    inner: Arc<dyn LlmProvider>,
+    retry: RetryConfig,
+    breaker: CircuitBreaker,
+}
+
+impl ResilientProvider {
+    pub fn new(
+        inner: Arc<dyn LlmProvider>,
+        retry: RetryConfig,
+        circuit_breaker: CircuitBreakerConfig,
+    ) -> Self {
+        Self {
+            inner,
+            retry,
+            breaker: CircuitBreaker::new(circuit_breaker),
+        }
+    }
+
+    /// Exponential backoff with jitter over the upper half of the delay.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .retry
+            .initial_backoff_ms
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.retry.max_backoff_ms);
+        Duration::from_millis(rand::rng().random_range(delay / 2..=delay))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ResilientProvider {
+    #[instrument(skip(self, request), fields(kind = ?request.kind))]
+    async fn complete(&self, request: &LlmRequest) -> Result<LlmResponse> {
+        let permit = self.breaker.acquire()?;
+
+        let mut attempt = 0;
+        loop {
+            let error = match self.inner.complete(request).await {
+                Ok(response) => {
+                    permit.record_success();
+                    return Ok(response);
+                }
+                Err(e) => e,
+            };
+
+            // Anything else means the provider answered, so it is up.
+            let Some(transient) = error.downcast_ref::<TransientError>() else {
+                permit.record_success();
+                return Err(error);
+            };
+
+            if attempt >= self.retry.max_retries {
+                permit.record_failure();
+                return Err(anyhow!(
+                    "LLM request failed after {} attempts: {}",
+                    attempt + 1,
+                    error
+                ));
+            }
+
+            let delay = match transient.retry_after {
+                Some(retry_after)
+                    if retry_after.as_millis() > self.retry.max_backoff_ms as u128 =>
+                {
+                    permit.record_failure();
+                    return Err(anyhow!(
+                        "LLM provider asked to retry in {}s: {}",
+                        retry_after.as_secs(),
+                        error
+                    ));
+                }
+                Some(retry_after) => retry_after,
+                None => self.backoff(attempt),
+            };
+
+            attempt += 1;
+            warn!(
+                error = %transient,
+                "Transient LLM failure, retry {}/{} in {}ms",
+                attempt,
+                self.retry.max_retries,
+                delay.as_millis()
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    fn model(&self, kind: ModelKind) -> String {
+        self.inner.model(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(open_secs: u64) -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            open_secs,
+        })
+    }
+
+    fn fail_twice(breaker: &CircuitBreaker) {
+        for _ in 0..2 {
+            breaker.acquire().unwrap().record_failure();
+        }
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = breaker(60);
+        breaker.acquire().unwrap().record_failure();
+        let permit = breaker.acquire().unwrap();
+
+        permit.record_failure();
+
+        assert!(breaker.acquire().is_err());
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let breaker = breaker(60);
+        breaker.acquire().unwrap().record_failure();
+        breaker.acquire().unwrap().record_success();
+        breaker.acquire().unwrap().record_failure();
+
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[test]
+    fn half_open_lets_a_single_probe_through() {
+        let breaker = breaker(0);
+        fail_twice(&breaker);
+
+        let probe = breaker.acquire().unwrap();
+        assert!(breaker.acquire().is_err());
+
+        probe.record_success();
+        assert!(breaker.acquire().is_ok());
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[test]
+    fn failed_probe_opens_again() {
+        let breaker = breaker(60);
+        fail_twice(&breaker);
+        breaker.state.lock().unwrap().open_until = Some(Instant::now());
+
+        breaker.acquire().unwrap().record_failure();
+
+        assert!(breaker.acquire().is_err());
+    }
+
+    #[test]
+    fn dropped_probe_lets_the_next_call_probe() {
+        let breaker = breaker(0);
+        fail_twice(&breaker);
+
+        drop(breaker.acquire().unwrap());
+
+        assert!(!breaker.state.lock().unwrap().probing);
+        breaker.acquire().unwrap().record_success();
+        assert!(breaker.state.lock().unwrap().open_until.is_none());
+    }
+
+    #[test]
+    fn disabled_breaker_never_opens() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 0,
+            open_secs: 60,
+        });
+        for _ in 0..10 {
+            breaker.acquire().unwrap().record_failure();
+        }
+
+        assert!(breaker.acquire().is_ok());
+    }
+
+    /// Answers with transient failures, then succeeds.
+    struct Flaky {
+        failures: Mutex<u32>,
+        retry_after: Option<Duration>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for Flaky {
+        async fn complete(&self, _request: &LlmRequest) -> Result<LlmResponse> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures == 0 {
+                return Ok(LlmResponse {
+                    content: "{}".to_owned(),
+                    usage: None,
+                });
+            }
+            *failures -= 1;
+            Err(TransientError {
+                message: "503".to_owned(),
+                retry_after: self.retry_after,
+            }
+            .into())
+        }
+
+        fn model(&self, _kind: ModelKind) -> String {
+            "flaky".to_owned()
+        }
+    }
+
+    fn provider(failures: u32, retry_after: Option<Duration>) -> ResilientProvider {
+        ResilientProvider::new(
+            Arc::new(Flaky {
+                failures: Mutex::new(failures),
+                retry_after,
+            }),
+            RetryConfig {
+                max_retries: 2,
+                initial_backoff_ms: 1,
+                max_backoff_ms: 100,
+            },
+            CircuitBreakerConfig::default(),
+        )
+    }
+
+    fn request() -> LlmRequest {
+        LlmRequest {
+            kind: ModelKind::Text,
+            prompt: String::new(),
+            image: None,
+            temperature: None,
+            schema: None,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let provider = provider(0, None);
+
+        for (attempt, max) in [(0, 1), (3, 8), (6, 64), (7, 100), (40, 100)] {
+            let delay = provider.backoff(attempt).as_millis() as u64;
+            assert!((max / 2..=max).contains(&delay), "{attempt}: {delay}ms");
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        assert!(provider(2, None).complete(&request()).await.is_ok());
+        assert!(provider(3, None).complete(&request()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn retry_after_beyond_the_maximum_backoff_fails_at_once() {
+        let short = provider(1, Some(Duration::from_millis(5)));
+        let long = provider(1, Some(Duration::from_secs(60)));
+
+        assert!(short.complete(&request()).await.is_ok());
+        let error = long.complete(&request()).await.unwrap_err();
+        assert!(error.to_string().contains("retry in 60s"), "{error}");
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_dates() {
+        assert_eq!(parse_retry_after(" 120 "), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
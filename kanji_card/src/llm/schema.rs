@@ -0,0 +1,73 @@
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// JSON schema the model's answer has to follow, sent as the OpenAI
+/// `response_format`.
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    pub name: String,
+    pub schema: Value,
+}
+
+impl ResponseSchema {
+    /// Derives the schema from the OpenAPI schema of `T`. Nested types must be
+    /// `#[schema(inline)]`, since the schema is sent without components.
+    pub fn of<T: ToSchema>() -> Self {
+        let mut schema = serde_json::to_value(T::schema()).unwrap_or(Value::Null);
+        make_strict(&mut schema);
+        Self {
+            name: T::name().into_owned(),
+            schema,
+        }
+    }
+}
+
+/// Strict structured outputs require every object to be closed and to list
+/// all of its properties as required; optional fields stay nullable.
+fn make_strict(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Object(properties)) = map.get("properties") {
+                let required = properties.keys().cloned().map(Value::String).collect();
+                map.insert("required".to_owned(), Value::Array(required));
+                map.insert("additionalProperties".to_owned(), Value::Bool(false));
+            }
+            map.values_mut().for_each(make_strict);
+        }
+        Value::Array(items) => items.iter_mut().for_each(make_strict),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::WordsResponse;
+    use serde_json::json;
+
+    #[test]
+    fn every_object_is_closed_and_requires_all_properties() {
+        let schema = ResponseSchema::of::<WordsResponse>();
+        let word = &schema.schema["properties"]["words"]["items"];
+
+        assert_eq!(schema.name, "WordsResponse");
+        assert_eq!(schema.schema["required"], json!(["words"]));
+        assert_eq!(schema.schema["additionalProperties"], json!(false));
+        assert_eq!(word["required"], json!(["reading", "translation", "word"]));
+        assert_eq!(word["additionalProperties"], json!(false));
+    }
+
+    #[test]
+    fn values_without_properties_are_left_alone() {
+        let mut schema = json!({
+            "type": "object",
+            "properties": { "tags": { "type": "array", "items": { "type": "string" } } },
+        });
+        make_strict(&mut schema);
+
+        assert_eq!(
+            schema["properties"]["tags"],
+            json!({ "type": "array", "items": { "type": "string" } })
+        );
+    }
+}
@@ -1,12 +1,20 @@
+mod account;
+mod anki;
 mod config;
+mod dictionary;
 mod environment;
+mod job;
 mod llm;
 mod rule;
+mod storage;
+#[cfg(test)]
+mod test_support;
+mod usage;
 mod user_repository;
 mod web_ui;
 mod word;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use axum::{
     http::{
         Method,
@@ -14,12 +22,12 @@ use axum::{
     },
     routing::get,
 };
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use llm::LlmService;
 use opentelemetry::global;
 use opentelemetry_otlp::{Protocol, WithExportConfig};
 use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
-use std::net::Ipv4Addr;
+use std::{net::Ipv4Addr, path::PathBuf, sync::Arc};
 use tokio::fs;
 use tower_http::cors::CorsLayer;
 use tracing::info;
@@ -28,11 +36,16 @@ use utoipa_axum::router::OpenApiRouter;
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
+    account::account_service::AccountService,
     config::Settings,
+    dictionary::dictionary_service::DictionaryService,
+    job::job_service::JobService,
     rule::{rule_repository, rule_service::RuleService},
+    storage::Repositories,
+    usage::usage_service::UsageService,
     word::{
-        set_repository::LearnSetRepository, set_service::SetService,
-        word_release_repository::WordReleaseRepository,
+        import::{FieldMapping, ImportFormat},
+        set_service::SetService,
     },
 };
 
@@ -46,6 +59,67 @@ use crate::{
 struct Args {
     #[arg(long)]
     generate_openapi: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Build the local dictionary index from a JMdict XML file
+    ImportJmdict {
+        #[arg(long)]
+        input: PathBuf,
+    },
+    /// Build the local kanji index from a KANJIDIC2 XML file
+    ImportKanjidic {
+        #[arg(long)]
+        input: PathBuf,
+    },
+    /// Export a user's sets and released words to an Anki package
+    ExportApkg {
+        #[arg(long)]
+        login: String,
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Write a versioned JSON backup of a whole account
+    ExportAccount {
+        #[arg(long)]
+        login: String,
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Restore an account backup, optionally into a different login
+    RestoreAccount {
+        #[arg(long)]
+        input: PathBuf,
+        /// Target login, defaults to the one stored in the backup
+        #[arg(long)]
+        login: Option<String>,
+    },
+    /// Import words from an Anki package or CSV/TSV file into a user's Tobe sets
+    Import {
+        #[arg(long)]
+        login: String,
+        #[arg(long)]
+        input: PathBuf,
+        /// Defaults to the input file extension
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+        /// Field name or 1-based column of the word
+        #[arg(long)]
+        word: Option<String>,
+        /// Field name or 1-based column of the translation
+        #[arg(long)]
+        translation: Option<String>,
+        /// Field name or 1-based column of the reading
+        #[arg(long)]
+        reading: Option<String>,
+        /// Delimited file has no header row
+        #[arg(long)]
+        no_header: bool,
+    },
 }
 
 #[tokio::main]
@@ -55,33 +129,127 @@ async fn main() -> Result<()> {
     let args = Args::parse();
     let settings = Settings::load()?;
 
-    let user_repository = user_repository::UserRepository::new().await?;
-    let rule_repository = rule_repository::RuleRepository::new().await?;
+    match &args.command {
+        Some(Command::ImportJmdict { input }) => {
+            let count = DictionaryService::import_jmdict(input, &settings.dictionary).await?;
+            println!("Dictionary index built with {} entries", count);
+            return Ok(());
+        }
+        Some(Command::ImportKanjidic { input }) => {
+            let count = DictionaryService::import_kanjidic(input, &settings.dictionary).await?;
+            println!("Kanji index built with {} entries", count);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let repositories = Repositories::new(&settings.storage).await?;
     let jwt_config = settings.jwt_config();
-    let llm_service = LlmService::new(
-        settings.openrouter.base_url.clone(),
-        settings.openrouter.api_key.clone(),
-        settings.openrouter.text_model.clone(),
-        settings.openrouter.image_model.clone(),
-        settings.openrouter.reasoning_model.clone(),
-        settings.openrouter.max_completion_tokens,
-    );
-
-    let release_repository = WordReleaseRepository::new().await?;
-    let set_repository = LearnSetRepository::new().await?;
-    let set_service = SetService::new(
-        set_repository.clone(),
-        release_repository.clone(),
+    let usage_service = Arc::new(UsageService::new(
+        repositories.usage.clone(),
+        settings.quota.clone(),
+    ));
+    let llm_service = LlmService::from_settings(&settings)?.with_usage(usage_service.clone());
+
+    let dictionary = DictionaryService::load(&settings.dictionary).await?;
+
+    let set_service = Arc::new(SetService::new(
+        repositories.sets.clone(),
+        repositories.releases.clone(),
+        repositories.review_logs.clone(),
+        repositories.set_starts.clone(),
+        repositories.settings.clone(),
         llm_service.clone(),
+        dictionary.clone(),
         settings.clone(),
-    );
+    ));
+
+    let account_service = AccountService::new(repositories.clone());
 
-    let rule_service = RuleService::new(rule_repository.clone(), llm_service, settings.clone());
+    match &args.command {
+        Some(Command::ExportAccount { login, output }) => {
+            let backup = account_service.export(login, true).await?;
+            fs::write(output, serde_json::to_vec_pretty(&backup)?).await?;
+            println!("Account {} written to {}", login, output.display());
+            return Ok(());
+        }
+        Some(Command::RestoreAccount { input, login }) => {
+            let backup = serde_json::from_slice(&fs::read(input).await?)?;
+            let report = account_service
+                .restore(backup, login.as_deref(), true)
+                .await?;
+            println!(
+                "Restored {} sets, {} released words, {} rules, {} reviews",
+                report.sets, report.released_words, report.rules, report.reviews
+            );
+            return Ok(());
+        }
+        Some(Command::ExportApkg { login, output }) => {
+            let package = set_service.export_apkg(login).await?;
+            fs::write(output, package).await?;
+            println!("Anki package written to {}", output.display());
+            return Ok(());
+        }
+        Some(Command::Import {
+            login,
+            input,
+            format,
+            word,
+            translation,
+            reading,
+            no_header,
+        }) => {
+            let format = format
+                .or_else(|| ImportFormat::from_path(input))
+                .ok_or_else(|| anyhow!("Unknown import format, pass --format"))?;
+            let mapping = FieldMapping {
+                word: word.clone(),
+                translation: translation.clone(),
+                reading: reading.clone(),
+                has_header: !no_header,
+            };
+            let content = fs::read(input).await?;
+            let report = set_service
+                .import_words(login, format, mapping, content)
+                .await?;
+            println!(
+                "Imported {}, skipped {}, duplicates {}",
+                report.imported, report.skipped, report.duplicates
+            );
+            return Ok(());
+        }
+        Some(Command::ImportJmdict { .. } | Command::ImportKanjidic { .. }) | None => {}
+    }
+
+    let rule_service = Arc::new(RuleService::new(
+        repositories.rules.clone(),
+        llm_service,
+        settings.clone(),
+    ));
+    let job_service = JobService::start(
+        repositories.jobs.clone(),
+        set_service.clone(),
+        rule_service.clone(),
+        settings.jobs.clone(),
+    )
+    .await?;
 
     let open_api_router = OpenApiRouter::new()
         .nest(
             "/api/auth",
-            auth_api::jwt_api_router(user_repository, jwt_config.clone()),
+            auth_api::jwt_api_router(repositories.users.clone(), jwt_config.clone()),
+        )
+        .nest(
+            "/api/account",
+            account::api::account_api_router(account_service, jwt_config.clone()),
+        )
+        .nest(
+            "/api/usage",
+            usage::api::usage_api_router(usage_service, jwt_config.clone()),
+        )
+        .nest(
+            "/api/jobs",
+            job::api::job_api_router(job_service, jwt_config.clone()),
         )
         .nest(
             "/api/rule",
@@ -89,7 +257,7 @@ async fn main() -> Result<()> {
         )
         .nest(
             "/api/rule/query",
-            query::query_router(rule_repository.clone(), jwt_config.clone()),
+            query::query_router(repositories.rules.clone(), jwt_config.clone()),
         )
         .nest(
             "/api/word",
@@ -97,7 +265,36 @@ async fn main() -> Result<()> {
         )
         .nest(
             "/api/word/query",
-            word::query::query_router(set_repository, release_repository, jwt_config.clone()),
+            word::query::query_router(
+                repositories.sets.clone(),
+                repositories.releases.clone(),
+                repositories.review_logs.clone(),
+                repositories.settings.clone(),
+                jwt_config.clone(),
+            ),
+        )
+        .nest(
+            "/api/dictionary",
+            dictionary::api::query_router(dictionary.clone(), jwt_config.clone()),
+        )
+        .nest(
+            "/api/word/query/kanji",
+            word::kanji::kanji_router(
+                repositories.sets.clone(),
+                repositories.releases.clone(),
+                dictionary,
+                jwt_config.clone(),
+            ),
+        )
+        .nest(
+            "/api/word/query/stats",
+            word::stats::stats_router(
+                repositories.sets.clone(),
+                repositories.releases.clone(),
+                repositories.review_logs.clone(),
+                repositories.settings.clone(),
+                jwt_config.clone(),
+            ),
         );
 
     let (router, mut api) = open_api_router.split_for_parts();
@@ -1,19 +1,32 @@
 use anyhow::anyhow;
-use std::path::PathBuf;
+use async_trait::async_trait;
+use rusqlite::{OptionalExtension, params};
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
-use crate::rule::rule::GrammarRule;
+use crate::{rule::rule::GrammarRule, storage::SqliteStorage};
 
-const STORAGE_DIR: &str = "data/rule";
+const STORAGE_DIR: &str = "rule";
+
+#[async_trait]
+pub trait RuleRepository: Send + Sync {
+    async fn remove(&self, user_login: &str, rule_id: &str) -> anyhow::Result<()>;
+
+    async fn save(&self, user_login: &str, rule: &GrammarRule) -> anyhow::Result<()>;
+
+    async fn load(&self, user_login: &str, id: &str) -> anyhow::Result<GrammarRule>;
+
+    async fn list_all(&self, user_login: &str) -> anyhow::Result<Vec<GrammarRule>>;
+}
 
 #[derive(Clone)]
-pub struct RuleRepository {
+pub struct FileRuleRepository {
     storage_dir: PathBuf,
 }
 
-impl RuleRepository {
-    pub async fn new() -> anyhow::Result<Self> {
-        let storage_dir = PathBuf::from(STORAGE_DIR);
+impl FileRuleRepository {
+    pub async fn new(data_dir: &Path) -> anyhow::Result<Self> {
+        let storage_dir = data_dir.join(STORAGE_DIR);
         fs::create_dir_all(&storage_dir).await?;
 
         let repository = Self { storage_dir };
@@ -23,8 +36,11 @@ impl RuleRepository {
     fn get_user_path(&self, user_login: &str) -> PathBuf {
         self.storage_dir.join(user_login)
     }
+}
 
-    pub async fn remove(&self, user_login: &str, rule_id: &str) -> anyhow::Result<()> {
+#[async_trait]
+impl RuleRepository for FileRuleRepository {
+    async fn remove(&self, user_login: &str, rule_id: &str) -> anyhow::Result<()> {
         let file_path = self
             .get_user_path(user_login)
             .join(format!("{rule_id}.json"));
@@ -33,17 +49,17 @@ impl RuleRepository {
         Ok(())
     }
 
-    pub async fn save(&self, user_login: &str, rule: &GrammarRule) -> anyhow::Result<()> {
+    async fn save(&self, user_login: &str, rule: &GrammarRule) -> anyhow::Result<()> {
         let json = serde_json::to_string_pretty(rule)?;
-        let file_path = self
-            .get_user_path(user_login)
-            .join(format!("{}.json", rule.id()));
+        let user_dir = self.get_user_path(user_login);
+        fs::create_dir_all(&user_dir).await?;
+        let file_path = user_dir.join(format!("{}.json", rule.id()));
 
         fs::write(file_path, json).await?;
         Ok(())
     }
 
-    pub async fn load(&self, user_login: &str, id: &str) -> anyhow::Result<GrammarRule> {
+    async fn load(&self, user_login: &str, id: &str) -> anyhow::Result<GrammarRule> {
         let file_path = self.get_user_path(user_login).join(format!("{id}.json"));
         if file_path.exists() {
             let json = fs::read_to_string(file_path).await?;
@@ -52,16 +68,7 @@ impl RuleRepository {
         Err(anyhow!("Rule not found"))
     }
 
-    pub async fn list_ids(&self, user_login: &str) -> anyhow::Result<Vec<String>> {
-        Ok(self
-            .list_all(user_login)
-            .await?
-            .into_iter()
-            .map(|x| x.id().to_owned())
-            .collect())
-    }
-
-    pub async fn list_all(&self, user_login: &str) -> anyhow::Result<Vec<GrammarRule>> {
+    async fn list_all(&self, user_login: &str) -> anyhow::Result<Vec<GrammarRule>> {
         let mut ids = Vec::new();
         let state_dir = self.get_user_path(user_login);
 
@@ -79,11 +86,111 @@ impl RuleRepository {
         let mut all_sets = Vec::new();
 
         for id in ids {
-            if let Ok(set) = self.load(user_login, &id).await {
-                all_sets.push(set);
-            }
+            all_sets.push(self.load(user_login, &id).await?);
         }
 
         Ok(all_sets)
     }
 }
+
+#[derive(Clone)]
+pub struct SqliteRuleRepository {
+    storage: SqliteStorage,
+}
+
+impl SqliteRuleRepository {
+    pub async fn new(storage: SqliteStorage) -> anyhow::Result<Self> {
+        storage
+            .call(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS rules (
+                        user_login TEXT NOT NULL,
+                        id TEXT NOT NULL,
+                        data TEXT NOT NULL,
+                        PRIMARY KEY (user_login, id)
+                    );",
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(Self { storage })
+    }
+}
+
+#[async_trait]
+impl RuleRepository for SqliteRuleRepository {
+    async fn remove(&self, user_login: &str, rule_id: &str) -> anyhow::Result<()> {
+        let user_login = user_login.to_owned();
+        let rule_id = rule_id.to_owned();
+        self.storage
+            .call(move |conn| {
+                let removed = conn.execute(
+                    "DELETE FROM rules WHERE user_login = ?1 AND id = ?2",
+                    params![user_login, rule_id],
+                )?;
+                if removed == 0 {
+                    return Err(anyhow!("Rule not found"));
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn save(&self, user_login: &str, rule: &GrammarRule) -> anyhow::Result<()> {
+        let json = serde_json::to_string(rule)?;
+        let user_login = user_login.to_owned();
+        let id = rule.id().to_owned();
+        self.storage
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO rules (user_login, id, data) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (user_login, id) DO UPDATE SET data = excluded.data",
+                    params![user_login, id, json],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn load(&self, user_login: &str, id: &str) -> anyhow::Result<GrammarRule> {
+        let user_login = user_login.to_owned();
+        let id = id.to_owned();
+        let json = self
+            .storage
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT data FROM rules WHERE user_login = ?1 AND id = ?2",
+                        params![user_login, id],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .optional()?)
+            })
+            .await?;
+
+        match json {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Err(anyhow!("Rule not found")),
+        }
+    }
+
+    async fn list_all(&self, user_login: &str) -> anyhow::Result<Vec<GrammarRule>> {
+        let user_login = user_login.to_owned();
+        let rows = self
+            .storage
+            .call(move |conn| {
+                let mut statement = conn.prepare("SELECT data FROM rules WHERE user_login = ?1")?;
+                let rows = statement
+                    .query_map(params![user_login], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<_, _>>()?)
+    }
+}
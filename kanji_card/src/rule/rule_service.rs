@@ -2,17 +2,23 @@ use crate::config::Settings;
 use crate::llm::{GrammarRuleResponse, LlmService};
 use crate::rule::rule::{GrammarRule, RuleExample, RuleTest};
 use crate::rule_repository::RuleRepository;
+use crate::usage::record::UsageEndpoint;
 use anyhow::Result;
+use std::sync::Arc;
 use tracing::{info, instrument};
 
 pub struct RuleService {
-    rule_repository: RuleRepository,
+    rule_repository: Arc<dyn RuleRepository>,
     llm_service: LlmService,
     config: Settings,
 }
 
 impl RuleService {
-    pub fn new(rule_repository: RuleRepository, llm_service: LlmService, config: Settings) -> Self {
+    pub fn new(
+        rule_repository: Arc<dyn RuleRepository>,
+        llm_service: LlmService,
+        config: Settings,
+    ) -> Self {
         Self {
             rule_repository,
             llm_service,
@@ -28,7 +34,9 @@ impl RuleService {
     ) -> Result<GrammarRule> {
         info!("Creating grammar rule from Japanese text");
 
-        let llm_response = self.extract_grammar_rule_from_text(japanese_text).await?;
+        let llm_response = self
+            .extract_grammar_rule_from_text(user_login, japanese_text)
+            .await?;
 
         let part_of_speech = llm_response.part_of_speech;
 
@@ -72,7 +80,7 @@ impl RuleService {
         info!("Creating grammar rule from description");
 
         let llm_response = self
-            .generate_grammar_rule_from_description(rule_description)
+            .generate_grammar_rule_from_description(user_login, rule_description)
             .await?;
 
         let part_of_speech = llm_response.part_of_speech;
@@ -148,6 +156,7 @@ impl RuleService {
     #[instrument(skip(self))]
     async fn extract_grammar_rule_from_text(
         &self,
+        user_login: &str,
         japanese_text: &str,
     ) -> Result<GrammarRuleResponse> {
         info!("Extracting grammar rule from Japanese text");
@@ -158,8 +167,11 @@ impl RuleService {
             .extract_grammar_rule_from_text
             .replace("{text}", japanese_text);
 
-        let response: GrammarRuleResponse =
-            self.llm_service.send_reasoning_request(&prompt).await?;
+        let response: GrammarRuleResponse = self
+            .llm_service
+            .for_user(user_login, UsageEndpoint::CreateRuleFromText)
+            .send_reasoning_request(&prompt)
+            .await?;
         info!("Successfully extracted grammar rule: {}", response.title);
 
         Ok(response)
@@ -168,6 +180,7 @@ impl RuleService {
     #[instrument(skip(self))]
     async fn generate_grammar_rule_from_description(
         &self,
+        user_login: &str,
         rule_description: &str,
     ) -> Result<GrammarRuleResponse> {
         info!("Generating grammar rule from description");
@@ -178,10 +191,81 @@ impl RuleService {
             .generate_grammar_rule_from_description
             .replace("{description}", rule_description);
 
-        let response: GrammarRuleResponse =
-            self.llm_service.send_reasoning_request(&prompt).await?;
+        let response: GrammarRuleResponse = self
+            .llm_service
+            .for_user(user_login, UsageEndpoint::CreateRuleFromDescription)
+            .send_reasoning_request(&prompt)
+            .await?;
         info!("Successfully generated grammar rule: {}", response.title);
 
         Ok(response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        llm::{ModelKind, mock::Fixture},
+        test_support::{USER, fixture, rule_service},
+    };
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn rule_fixture(contains: &str, title: &str) -> Fixture {
+        let response = json!({
+            "title": title,
+            "conspect": "Выражает желание говорящего.",
+            "part_of_speech": "Jodoushi",
+            "examples": [{
+                "title": "Желание",
+                "content": "水が飲みたい",
+                "description": "Хочу пить воду",
+                "content_translation": "Хочу пить воду",
+            }],
+            "tests": [{
+                "rus_description": "Хочу есть",
+                "question_content": "食べ＿",
+                "answer": "たい",
+            }],
+        });
+        fixture(contains, Some(ModelKind::Reasoning), response)
+    }
+
+    #[tokio::test]
+    async fn creates_and_saves_rule_from_text() {
+        let dir = TempDir::new().unwrap();
+        let (service, repository) = rule_service(
+            dir.path(),
+            vec![rule_fixture("Rule of: 水が飲みたい", "〜たい")],
+        )
+        .await;
+
+        let rule = service
+            .create_from_text(USER, "水が飲みたい")
+            .await
+            .unwrap();
+
+        assert_eq!(rule.title(), "〜たい");
+        assert_eq!(rule.examples().len(), 1);
+        let saved = repository.list_all(USER).await.unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].id(), rule.id());
+    }
+
+    #[tokio::test]
+    async fn creates_rule_from_description() {
+        let dir = TempDir::new().unwrap();
+        let (service, _) = rule_service(
+            dir.path(),
+            vec![rule_fixture("Rule for: желание", "〜たい (желание)")],
+        )
+        .await;
+
+        let rule = service
+            .create_from_description(USER, "желание")
+            .await
+            .unwrap();
+
+        assert_eq!(rule.title(), "〜たい (желание)");
+    }
+}
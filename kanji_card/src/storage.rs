@@ -0,0 +1,127 @@
+use anyhow::{Result, anyhow};
+use rusqlite::Connection;
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tokio::fs;
+use tracing::info;
+
+use crate::{
+    config::{StorageBackend, StorageConfig},
+    job::job_repository::{FileJobRepository, JobRepository, SqliteJobRepository},
+    rule::rule_repository::{FileRuleRepository, RuleRepository, SqliteRuleRepository},
+    usage::usage_repository::{FileUsageRepository, SqliteUsageRepository, UsageRepository},
+    user_repository::{FileUserRepository, SqliteUserRepository, UserRepository},
+    word::{
+        review_log_repository::{
+            FileReviewLogRepository, ReviewLogRepository, SqliteReviewLogRepository,
+        },
+        set_repository::{FileLearnSetRepository, LearnSetRepository, SqliteLearnSetRepository},
+        set_start_repository::{
+            FileSetStartRepository, SetStartRepository, SqliteSetStartRepository,
+        },
+        settings_repository::{
+            FileLearningSettingsRepository, LearningSettingsRepository,
+            SqliteLearningSettingsRepository,
+        },
+        word_release_repository::{
+            FileWordReleaseRepository, SqliteWordReleaseRepository, WordReleaseRepository,
+        },
+    },
+};
+
+/// Shared handle to the embedded SQLite database.
+///
+/// `rusqlite` is synchronous, so every statement runs on the blocking pool
+/// behind a single connection lock.
+#[derive(Clone)]
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    pub async fn open(path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let path = path.to_owned();
+        let connection = tokio::task::spawn_blocking(move || -> Result<Connection> {
+            let connection = Connection::open(path)?;
+            connection.pragma_update(None, "journal_mode", "WAL")?;
+            connection.pragma_update(None, "synchronous", "NORMAL")?;
+            Ok(connection)
+        })
+        .await??;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    pub async fn call<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection
+                .lock()
+                .map_err(|_| anyhow!("SQLite connection lock poisoned"))?;
+            f(&mut connection)
+        })
+        .await?
+    }
+}
+
+#[derive(Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepository>,
+    pub rules: Arc<dyn RuleRepository>,
+    pub sets: Arc<dyn LearnSetRepository>,
+    pub releases: Arc<dyn WordReleaseRepository>,
+    pub review_logs: Arc<dyn ReviewLogRepository>,
+    pub set_starts: Arc<dyn SetStartRepository>,
+    pub usage: Arc<dyn UsageRepository>,
+    pub jobs: Arc<dyn JobRepository>,
+    pub settings: Arc<dyn LearningSettingsRepository>,
+}
+
+impl Repositories {
+    pub async fn new(config: &StorageConfig) -> Result<Self> {
+        match config.backend {
+            StorageBackend::File => {
+                info!("Using file storage in {}", config.data_dir);
+                let data_dir = Path::new(&config.data_dir);
+                Ok(Self {
+                    users: Arc::new(FileUserRepository::new(data_dir).await?),
+                    rules: Arc::new(FileRuleRepository::new(data_dir).await?),
+                    sets: Arc::new(FileLearnSetRepository::new(data_dir).await?),
+                    releases: Arc::new(FileWordReleaseRepository::new(data_dir).await?),
+                    review_logs: Arc::new(FileReviewLogRepository::new(data_dir).await?),
+                    set_starts: Arc::new(FileSetStartRepository::new(data_dir).await?),
+                    usage: Arc::new(FileUsageRepository::new(data_dir).await?),
+                    jobs: Arc::new(FileJobRepository::new(data_dir).await?),
+                    settings: Arc::new(FileLearningSettingsRepository::new(data_dir).await?),
+                })
+            }
+            StorageBackend::Sqlite => {
+                info!("Using SQLite storage at {}", config.sqlite_path);
+                let storage = SqliteStorage::open(&config.sqlite_path).await?;
+                Ok(Self {
+                    users: Arc::new(SqliteUserRepository::new(storage.clone()).await?),
+                    rules: Arc::new(SqliteRuleRepository::new(storage.clone()).await?),
+                    sets: Arc::new(SqliteLearnSetRepository::new(storage.clone()).await?),
+                    releases: Arc::new(SqliteWordReleaseRepository::new(storage.clone()).await?),
+                    review_logs: Arc::new(SqliteReviewLogRepository::new(storage.clone()).await?),
+                    set_starts: Arc::new(SqliteSetStartRepository::new(storage.clone()).await?),
+                    usage: Arc::new(SqliteUsageRepository::new(storage.clone()).await?),
+                    jobs: Arc::new(SqliteJobRepository::new(storage.clone()).await?),
+                    settings: Arc::new(SqliteLearningSettingsRepository::new(storage).await?),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Services wired to file storage in a temporary directory and to the mock
+//! LLM provider, for tests.
+
+use lindera::dictionary::{DictionaryBuilder, Metadata};
+use serde_json::Value;
+use std::{fs, path::Path, sync::Arc};
+
+use crate::{
+    config::Settings,
+    dictionary::dictionary_service::DictionaryService,
+    llm::{
+        LlmService, ModelKind,
+        mock::{Fixture, MockProvider},
+    },
+    rule::{
+        rule_repository::{FileRuleRepository, RuleRepository},
+        rule_service::RuleService,
+    },
+    word::{
+        review_log_repository::FileReviewLogRepository, set_repository::FileLearnSetRepository,
+        set_service::SetService, set_start_repository::FileSetStartRepository,
+        settings_repository::FileLearningSettingsRepository,
+        word_release_repository::FileWordReleaseRepository,
+    },
+};
+
+pub const USER: &str = "user";
+
+/// Defaults with placeholder credentials and prompts that only wrap their
+/// input.
+pub fn settings() -> Settings {
+    serde_json::from_value(serde_json::json!({
+        "server": { "domain": "localhost", "port": 0 },
+        "openrouter": {
+            "base_url": "http://localhost",
+            "api_key": "",
+            "text_model": "text",
+            "image_model": "image",
+            "reasoning_model": "reasoning",
+            "max_completion_tokens": 1000,
+        },
+        "jwt": { "secret_key": "secret", "token_expiry": 3600, "refresh_threshold": 600 },
+        "prompts": {
+            "extract_words_from_text": "Words of: {text}",
+            "extract_words_from_image": "Words of the image",
+            "extract_grammar_rule_from_text": "Rule of: {text}",
+            "generate_grammar_rule_from_description": "Rule for: {description}",
+        },
+    }))
+    .expect("test settings are valid")
+}
+
+pub fn fixture(contains: &str, kind: Option<ModelKind>, response: Value) -> Fixture {
+    Fixture {
+        contains: contains.to_owned(),
+        kind,
+        response,
+    }
+}
+
+fn llm(fixtures: Vec<Fixture>) -> LlmService {
+    LlmService::new(Arc::new(MockProvider::new(fixtures)), 0)
+}
+
+pub async fn set_service(dir: &Path, fixtures: Vec<Fixture>) -> SetService {
+    set_service_with_dictionary(dir, fixtures, DictionaryService::default()).await
+}
+
+pub async fn set_service_with_dictionary(
+    dir: &Path,
+    fixtures: Vec<Fixture>,
+    dictionary: DictionaryService,
+) -> SetService {
+    SetService::new(
+        Arc::new(FileLearnSetRepository::new(dir).await.unwrap()),
+        Arc::new(FileWordReleaseRepository::new(dir).await.unwrap()),
+        Arc::new(FileReviewLogRepository::new(dir).await.unwrap()),
+        Arc::new(FileSetStartRepository::new(dir).await.unwrap()),
+        Arc::new(FileLearningSettingsRepository::new(dir).await.unwrap()),
+        llm(fixtures),
+        dictionary,
+        settings(),
+    )
+}
+
+/// The service together with its repository, to check what was saved.
+pub async fn rule_service(
+    dir: &Path,
+    fixtures: Vec<Fixture>,
+) -> (RuleService, Arc<dyn RuleRepository>) {
+    let repository: Arc<dyn RuleRepository> = Arc::new(FileRuleRepository::new(dir).await.unwrap());
+    let service = RuleService::new(repository.clone(), llm(fixtures), settings());
+    (service, repository)
+}
+
+/// IPADIC rows: surface, context ids, cost, part of speech with details and
+/// conjugation, dictionary form, reading, pronunciation.
+const MORPHOLOGY_WORDS: &str = "\
+猫,0,0,100,名詞,一般,*,*,*,*,猫,ネコ,ネコ
+犬,0,0,100,名詞,一般,*,*,*,*,犬,イヌ,イヌ
+勉強,0,0,100,名詞,サ変接続,*,*,*,*,勉強,ベンキョウ,ベンキョウ
+食べ,0,0,100,動詞,自立,*,*,一段,連用形,食べる,タベ,タベ
+食べる,0,0,100,動詞,自立,*,*,一段,基本形,食べる,タベル,タベル
+し,0,0,100,動詞,自立,*,*,サ変・スル,連用形,する,シ,シ
+する,0,0,100,動詞,自立,*,*,サ変・スル,基本形,する,スル,スル
+ます,0,0,100,助動詞,*,*,*,特殊・マス,基本形,ます,マス,マス
+た,0,0,100,助動詞,*,*,*,特殊・タ,基本形,た,タ,タ
+が,0,0,100,助詞,格助詞,一般,*,*,*,が,ガ,ガ
+を,0,0,100,助詞,格助詞,一般,*,*,*,を,ヲ,ヲ
+。,0,0,100,記号,句点,*,*,*,*,。,。,。
+";
+
+/// Builds a Lindera dictionary of a handful of words into `dir` and
+/// returns its path.
+pub fn morphology_dictionary(dir: &Path) -> String {
+    let source = dir.join("morphology_source");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(
+        source.join("char.def"),
+        "DEFAULT 0 1 0\nHIRAGANA 0 1 2\nKATAKANA 1 1 2\nKANJI 0 0 2\n\
+         0x3041..0x309F HIRAGANA\n0x30A1..0x30FF KATAKANA\n0x4E00..0x9FFF KANJI\n",
+    )
+    .unwrap();
+    let unknown = ["DEFAULT", "HIRAGANA", "KATAKANA", "KANJI"]
+        .map(|x| format!("{x},0,0,10000,名詞,一般,*,*,*,*,*\n"))
+        .concat();
+    fs::write(source.join("unk.def"), unknown).unwrap();
+    fs::write(source.join("matrix.def"), "1 1\n0 0 0\n").unwrap();
+    fs::write(source.join("words.csv"), MORPHOLOGY_WORDS).unwrap();
+
+    let output = dir.join("morphology");
+    DictionaryBuilder::new(Metadata::default())
+        .build_dictionary(&source, &output)
+        .unwrap();
+    output.to_string_lossy().into_owned()
+}
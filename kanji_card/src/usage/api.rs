@@ -0,0 +1,56 @@
+use axum::{Extension, Json, extract::State, http::StatusCode, middleware};
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    environment::auth::{Claims, JwtConfig, auth_middleware},
+    usage::usage_service::{QuotaExceeded, UsageBudget, UsageService},
+};
+
+/// Status for a failed LLM-backed request: 429 when the quota ran out.
+pub fn llm_error_status(error: &anyhow::Error) -> StatusCode {
+    if error.is::<QuotaExceeded>() {
+        StatusCode::TOO_MANY_REQUESTS
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+#[derive(Clone)]
+struct ApiState {
+    usage_service: Arc<UsageService>,
+}
+
+pub fn usage_api_router(usage_service: Arc<UsageService>, jwt_config: JwtConfig) -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(get_budget))
+        .layer(middleware::from_fn_with_state(
+            jwt_config.clone(),
+            auth_middleware,
+        ))
+        .with_state(ApiState { usage_service })
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    responses(
+        (status = 200, description = "Tokens spent and left for today and this month", body = UsageBudget),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn get_budget(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<UsageBudget>, (StatusCode, String)> {
+    info!("Getting LLM budget of {}", claims.sub);
+    match state.usage_service.budget(&claims.sub).await {
+        Ok(budget) => Ok(Json(budget)),
+        Err(e) => {
+            error!("Failed to get LLM budget of {}: {}", claims.sub, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+    }
+}
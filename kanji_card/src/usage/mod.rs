@@ -0,0 +1,4 @@
+pub mod api;
+pub mod record;
+pub mod usage_repository;
+pub mod usage_service;
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+use utoipa::ToSchema;
+
+/// Features that spend LLM tokens on behalf of a user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageEndpoint {
+    ExtractWordsFromText,
+    ExtractWordsFromImage,
+    CreateRuleFromText,
+    CreateRuleFromDescription,
+}
+
+/// Rough UTF-8 bytes per token: ASCII words take about four characters per
+/// token, kana and kanji close to one.
+const BYTES_PER_TOKEN: usize = 4;
+/// Flat charge for an attached image when the provider reports no usage.
+const IMAGE_TOKENS: u64 = 1000;
+
+/// Tokens reported by the provider for one completion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Charged amount in credits, when the provider reports it.
+    pub cost: Option<f64>,
+}
+
+impl TokenUsage {
+    /// Approximates the usage of a call whose provider reports none, so that
+    /// such backends still count against the quota.
+    pub fn estimate(prompt: &str, has_image: bool, completion: &str) -> Self {
+        let tokens = |text: &str| text.len().div_ceil(BYTES_PER_TOKEN).max(1) as u64;
+        Self {
+            prompt_tokens: tokens(prompt) + if has_image { IMAGE_TOKENS } else { 0 },
+            completion_tokens: tokens(completion),
+            cost: None,
+        }
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+/// One provider call, including repair re-prompts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    id: String,
+    timestamp: DateTime<Utc>,
+    endpoint: UsageEndpoint,
+    model: String,
+    usage: TokenUsage,
+}
+
+impl UsageRecord {
+    pub fn new(endpoint: UsageEndpoint, model: String, usage: TokenUsage) -> Self {
+        Self {
+            id: Ulid::new().to_string(),
+            timestamp: Utc::now(),
+            endpoint,
+            model,
+            usage,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    pub fn endpoint(&self) -> UsageEndpoint {
+        self.endpoint
+    }
+
+    pub fn usage(&self) -> &TokenUsage {
+        &self.usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_counts_every_part_of_the_call() {
+        let text = TokenUsage::estimate("猫が好き", false, "{\"words\":[]}");
+        let image = TokenUsage::estimate("猫が好き", true, "{\"words\":[]}");
+
+        assert_eq!(text.prompt_tokens, 3);
+        assert_eq!(text.completion_tokens, 3);
+        assert_eq!(image.prompt_tokens, 3 + IMAGE_TOKENS);
+        assert_eq!(TokenUsage::estimate("", false, "").total_tokens(), 2);
+    }
+}
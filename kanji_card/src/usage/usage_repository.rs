@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::params;
+use std::path::{Path, PathBuf};
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::{storage::SqliteStorage, usage::record::UsageRecord};
+
+const STORAGE_DIR: &str = "usage";
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Append-only log of LLM calls per user, ordered by time.
+#[async_trait]
+pub trait UsageRepository: Send + Sync {
+    async fn append(&self, user_login: &str, record: &UsageRecord) -> anyhow::Result<()>;
+
+    /// Records at or after `from`.
+    async fn list_since(
+        &self,
+        user_login: &str,
+        from: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<UsageRecord>>;
+}
+
+#[derive(Clone)]
+pub struct FileUsageRepository {
+    storage_dir: PathBuf,
+}
+
+impl FileUsageRepository {
+    pub async fn new(data_dir: &Path) -> anyhow::Result<Self> {
+        let storage_dir = data_dir.join(STORAGE_DIR);
+        fs::create_dir_all(&storage_dir).await?;
+
+        Ok(Self { storage_dir })
+    }
+
+    fn get_user_path(&self, user_login: &str) -> PathBuf {
+        self.storage_dir.join(format!("{user_login}.jsonl"))
+    }
+}
+
+#[async_trait]
+impl UsageRepository for FileUsageRepository {
+    async fn append(&self, user_login: &str, record: &UsageRecord) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.get_user_path(user_login))
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn list_since(
+        &self,
+        user_login: &str,
+        from: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<UsageRecord>> {
+        let file_path = self.get_user_path(user_login);
+        if !file_path.exists() {
+            return Ok(vec![]);
+        }
+
+        let content = fs::read_to_string(file_path).await?;
+        let records = content
+            .lines()
+            .map(serde_json::from_str::<UsageRecord>)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(records
+            .into_iter()
+            .filter(|record| record.timestamp() >= from)
+            .collect())
+    }
+}
+
+#[derive(Clone)]
+pub struct SqliteUsageRepository {
+    storage: SqliteStorage,
+}
+
+impl SqliteUsageRepository {
+    pub async fn new(storage: SqliteStorage) -> anyhow::Result<Self> {
+        storage
+            .call(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS llm_usage (
+                        user_login TEXT NOT NULL,
+                        id TEXT NOT NULL,
+                        timestamp TEXT NOT NULL,
+                        data TEXT NOT NULL,
+                        PRIMARY KEY (user_login, id)
+                    );
+                    CREATE INDEX IF NOT EXISTS llm_usage_user_timestamp
+                        ON llm_usage (user_login, timestamp);",
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(Self { storage })
+    }
+}
+
+#[async_trait]
+impl UsageRepository for SqliteUsageRepository {
+    async fn append(&self, user_login: &str, record: &UsageRecord) -> anyhow::Result<()> {
+        let user_login = user_login.to_owned();
+        let id = record.id().to_owned();
+        let timestamp = format_timestamp(record.timestamp());
+        let json = serde_json::to_string(record)?;
+
+        self.storage
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO llm_usage (user_login, id, timestamp, data)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![user_login, id, timestamp, json],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn list_since(
+        &self,
+        user_login: &str,
+        from: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<UsageRecord>> {
+        let user_login = user_login.to_owned();
+        let from = format_timestamp(from);
+        let rows = self
+            .storage
+            .call(move |conn| {
+                let mut statement = conn.prepare(
+                    "SELECT data FROM llm_usage
+                     WHERE user_login = ?1 AND timestamp >= ?2
+                     ORDER BY timestamp, id",
+                )?;
+                let rows = statement
+                    .query_map(params![user_login, from], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<_, _>>()?)
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use serde::Serialize;
+use std::{cmp::Reverse, collections::HashMap, fmt, sync::Arc};
+use tracing::{info, instrument, warn};
+use utoipa::ToSchema;
+
+use crate::{
+    config::QuotaConfig,
+    usage::{
+        record::{TokenUsage, UsageEndpoint, UsageRecord},
+        usage_repository::UsageRepository,
+    },
+};
+
+/// Returned before calling the model when the user spent the budget.
+#[derive(Debug)]
+pub struct QuotaExceeded {
+    pub period: &'static str,
+    pub limit: u64,
+    pub resets_at: DateTime<Utc>,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The {} LLM quota of {} tokens is used up, it resets at {}",
+            self.period,
+            self.limit,
+            self.resets_at.to_rfc3339()
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PeriodBudget {
+    pub start: DateTime<Utc>,
+    pub resets_at: DateTime<Utc>,
+    pub requests: usize,
+    pub used_tokens: u64,
+    pub cost: f64,
+    /// `None` when the period is unlimited.
+    pub limit_tokens: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EndpointUsage {
+    pub endpoint: UsageEndpoint,
+    pub requests: usize,
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UsageBudget {
+    pub daily: PeriodBudget,
+    pub monthly: PeriodBudget,
+    /// Spending of the current month per feature.
+    pub endpoints: Vec<EndpointUsage>,
+}
+
+/// Token accounting and quota enforcement; periods are UTC days and months.
+pub struct UsageService {
+    repository: Arc<dyn UsageRepository>,
+    config: QuotaConfig,
+}
+
+fn day_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive().and_time(Default::default()).and_utc()
+}
+
+fn month_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(now.year(), now.month(), 1)
+        .unwrap_or(now.date_naive())
+        .and_time(Default::default())
+        .and_utc()
+}
+
+impl UsageService {
+    pub fn new(repository: Arc<dyn UsageRepository>, config: QuotaConfig) -> Self {
+        Self { repository, config }
+    }
+
+    fn limits(&self, user_login: &str) -> (Option<u64>, Option<u64>) {
+        if self.config.exempt_users.iter().any(|x| x == user_login) {
+            return (None, None);
+        }
+        let limit = |x: u64| (x > 0).then_some(x);
+        (
+            limit(self.config.daily_tokens),
+            limit(self.config.monthly_tokens),
+        )
+    }
+
+    /// Fails with [`QuotaExceeded`] when the user has no tokens left today or
+    /// this month. A call may overshoot the limit; the next one is refused.
+    #[instrument(skip(self))]
+    pub async fn check(&self, user_login: &str) -> Result<()> {
+        let (daily_limit, monthly_limit) = self.limits(user_login);
+        if daily_limit.is_none() && monthly_limit.is_none() {
+            return Ok(());
+        }
+
+        let budget = self.budget(user_login).await?;
+        for (period, budget) in [("daily", &budget.daily), ("monthly", &budget.monthly)] {
+            if let Some(limit) = budget.limit_tokens
+                && budget.used_tokens >= limit
+            {
+                warn!("User {} exceeded the {} LLM quota", user_login, period);
+                return Err(QuotaExceeded {
+                    period,
+                    limit,
+                    resets_at: budget.resets_at,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(self, usage))]
+    pub async fn record(
+        &self,
+        user_login: &str,
+        endpoint: UsageEndpoint,
+        model: String,
+        usage: TokenUsage,
+    ) -> Result<()> {
+        info!(
+            "User {} spent {} tokens on {:?} with {}",
+            user_login,
+            usage.total_tokens(),
+            endpoint,
+            model
+        );
+        self.repository
+            .append(user_login, &UsageRecord::new(endpoint, model, usage))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    pub async fn budget(&self, user_login: &str) -> Result<UsageBudget> {
+        let now = Utc::now();
+        let day_start = day_start(now);
+        let month_start = month_start(now);
+        let records = self.repository.list_since(user_login, month_start).await?;
+        let (daily_limit, monthly_limit) = self.limits(user_login);
+
+        let period = |start: DateTime<Utc>, resets_at: DateTime<Utc>, limit: Option<u64>| {
+            let records = records
+                .iter()
+                .filter(|x| x.timestamp() >= start)
+                .collect::<Vec<_>>();
+            let used_tokens = records.iter().map(|x| x.usage().total_tokens()).sum();
+            PeriodBudget {
+                start,
+                resets_at,
+                requests: records.len(),
+                used_tokens,
+                cost: records.iter().filter_map(|x| x.usage().cost).sum(),
+                limit_tokens: limit,
+                remaining_tokens: limit.map(|x: u64| x.saturating_sub(used_tokens)),
+            }
+        };
+
+        let mut endpoints: HashMap<UsageEndpoint, EndpointUsage> = HashMap::new();
+        for record in &records {
+            let entry = endpoints
+                .entry(record.endpoint())
+                .or_insert_with(|| EndpointUsage {
+                    endpoint: record.endpoint(),
+                    requests: 0,
+                    tokens: 0,
+                    cost: 0.0,
+                });
+            entry.requests += 1;
+            entry.tokens += record.usage().total_tokens();
+            entry.cost += record.usage().cost.unwrap_or_default();
+        }
+        let mut endpoints = endpoints.into_values().collect::<Vec<_>>();
+        endpoints.sort_by_key(|x| Reverse(x.tokens));
+
+        Ok(UsageBudget {
+            daily: period(day_start, day_start + Duration::days(1), daily_limit),
+            monthly: period(month_start, month_start + Months::new(1), monthly_limit),
+            endpoints,
+        })
+    }
+}
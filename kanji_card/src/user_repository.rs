@@ -1,29 +1,43 @@
 use anyhow::Result;
+use async_trait::async_trait;
+use rusqlite::{OptionalExtension, params};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
+use crate::storage::SqliteStorage;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub password_hash: String,
 }
 
-pub struct UserRepository {
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn save_user(&self, login: &str, password_hash: &str) -> Result<()>;
+
+    async fn get_user(&self, login: &str) -> Result<Option<User>>;
+}
+
+pub struct FileUserRepository {
     base_path: PathBuf,
 }
 
-const STORAGE_DIR: &str = "data/users";
+const STORAGE_DIR: &str = "users";
 
-impl UserRepository {
-    pub async fn new() -> anyhow::Result<Self> {
-        let storage_dir = PathBuf::from(STORAGE_DIR);
+impl FileUserRepository {
+    pub async fn new(data_dir: &Path) -> anyhow::Result<Self> {
+        let storage_dir = data_dir.join(STORAGE_DIR);
         fs::create_dir_all(&storage_dir).await?;
         Ok(Self {
             base_path: storage_dir,
         })
     }
+}
 
-    pub async fn save_user(&self, login: &str, password_hash: &str) -> Result<()> {
+#[async_trait]
+impl UserRepository for FileUserRepository {
+    async fn save_user(&self, login: &str, password_hash: &str) -> Result<()> {
         let user_path = self.base_path.join(format!("{login}.json"));
         let user = User {
             password_hash: password_hash.to_string(),
@@ -33,7 +47,7 @@ impl UserRepository {
         Ok(())
     }
 
-    pub async fn get_user(&self, login: &str) -> Result<Option<User>> {
+    async fn get_user(&self, login: &str) -> Result<Option<User>> {
         let user_path = self.base_path.join(format!("{login}.json"));
         if !user_path.exists() {
             return Ok(None);
@@ -42,17 +56,63 @@ impl UserRepository {
         let user = serde_json::from_str(&content)?;
         Ok(Some(user))
     }
+}
 
-    pub async fn list_all_users(&self) -> Result<Vec<String>> {
-        let mut users = fs::read_dir(&self.base_path).await?;
-        let mut user_logins = vec![];
-        while let Some(entry) = users.next_entry().await? {
-            if let Some(file_name) = entry.file_name().to_str() {
-                if file_name.ends_with(".json") {
-                    user_logins.push(file_name.trim_end_matches(".json").to_string());
-                }
-            }
-        }
-        Ok(user_logins)
+pub struct SqliteUserRepository {
+    storage: SqliteStorage,
+}
+
+impl SqliteUserRepository {
+    pub async fn new(storage: SqliteStorage) -> anyhow::Result<Self> {
+        storage
+            .call(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS users (
+                        login TEXT PRIMARY KEY NOT NULL,
+                        password_hash TEXT NOT NULL
+                    );",
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(Self { storage })
+    }
+}
+
+#[async_trait]
+impl UserRepository for SqliteUserRepository {
+    async fn save_user(&self, login: &str, password_hash: &str) -> Result<()> {
+        let login = login.to_owned();
+        let password_hash = password_hash.to_owned();
+        self.storage
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO users (login, password_hash) VALUES (?1, ?2)
+                     ON CONFLICT (login) DO UPDATE SET password_hash = excluded.password_hash",
+                    params![login, password_hash],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_user(&self, login: &str) -> Result<Option<User>> {
+        let login = login.to_owned();
+        self.storage
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT password_hash FROM users WHERE login = ?1",
+                        params![login],
+                        |row| {
+                            Ok(User {
+                                password_hash: row.get(0)?,
+                            })
+                        },
+                    )
+                    .optional()?)
+            })
+            .await
     }
 }
@@ -1,13 +1,28 @@
-use crate::{environment::auth, llm::ExtractedWord, word::set_service::SetService};
+use crate::{
+    environment::auth,
+    llm::ExtractedWord,
+    usage::api::llm_error_status,
+    word::{
+        domain::{WordEdit, review::ReviewGrade, schedule::Rating, settings::LearningSettings},
+        duplicate::MergeProposal,
+        import::{FieldMapping, ImportFormat, ImportReport},
+        set_service::{SetService, WordError},
+    },
+};
 use auth::{Claims, JwtConfig, auth_middleware};
 use axum::{
     Json,
-    extract::{Extension, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{Extension, Query, State},
+    http::{
+        StatusCode,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    },
     middleware,
+    response::IntoResponse,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tracing::{error, info, instrument};
 use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
@@ -17,30 +32,60 @@ struct ApiState {
     set_service: Arc<SetService>,
 }
 
-pub fn set_api_router(set_service: SetService, jwt_config: JwtConfig) -> OpenApiRouter {
+#[allow(deprecated)]
+pub fn set_api_router(set_service: Arc<SetService>, jwt_config: JwtConfig) -> OpenApiRouter {
     OpenApiRouter::new()
         .routes(routes!(extract_words_from_text))
+        .routes(routes!(extract_new_words_from_text))
+        .routes(routes!(find_duplicates))
         .routes(routes!(extract_words_from_image))
         .routes(routes!(save_words))
         .routes(routes!(to_next_learn_iter))
+        .routes(routes!(review_set))
+        .routes(routes!(review_released_words))
         .routes(routes!(mark_as_tobe))
+        .routes(routes!(add_word))
+        .routes(routes!(edit_set_word, delete_set_word))
+        .routes(routes!(move_word))
+        .routes(routes!(edit_released_word, delete_released_word))
+        .routes(routes!(create_set))
+        .routes(routes!(delete_set))
+        .routes(routes!(merge_sets))
+        .routes(routes!(split_set))
+        .routes(routes!(reorder_set))
+        .routes(routes!(start_set))
+        .routes(routes!(get_learning_settings, update_learning_settings))
+        .routes(routes!(export_apkg))
+        .routes(routes!(import_words))
         .layer(middleware::from_fn_with_state(
             jwt_config.clone(),
             auth_middleware,
         ))
-        .with_state(ApiState {
-            set_service: Arc::new(set_service),
-        })
+        .with_state(ApiState { set_service })
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
 struct ExtractWordsFromTextRequest {
     text: String,
+    /// Asks the model again instead of reusing a cached answer.
+    #[serde(default)]
+    bypass_cache: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct NewWordsResponse {
+    words: Vec<ExtractedWord>,
+    /// Words of the text already in a set or released. They are cut out of the
+    /// prompt when morphology is loaded, else out of the answer.
+    known_words: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
 struct ExtractWordsFromImageRequest {
     image_data: Vec<u8>,
+    /// Asks the model again instead of reusing a cached answer.
+    #[serde(default)]
+    bypass_cache: bool,
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
@@ -60,60 +105,187 @@ pub struct RegisterRequest {
     password: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct WordGrade {
+    word_id: String,
+    rating: Rating,
+    response_time_ms: Option<u64>,
+}
+
+impl WordGrade {
+    fn into_entry(self) -> (String, ReviewGrade) {
+        (
+            self.word_id,
+            ReviewGrade {
+                rating: self.rating,
+                response_time_ms: self.response_time_ms,
+            },
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct ReviewSetRequest {
+    grades: Vec<WordGrade>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct ReviewReleasedRequest {
+    grades: Vec<WordGrade>,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
 struct MarkAsTobeRequest {
     word_ids: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct AddWordResponse {
+    id: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct MoveWordRequest {
+    target_set_id: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct CreateSetRequest {
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct CreatedSetResponse {
+    id: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct MergeSetsRequest {
+    /// Set whose words are moved; it is removed afterwards.
+    source_set_id: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct SplitSetRequest {
+    /// Words moved into the new set.
+    word_ids: Vec<String>,
+    name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+struct ReorderSetRequest {
+    /// Every word of the set in the new order.
+    word_ids: Vec<String>,
+}
+
+fn word_error_status(error: &anyhow::Error) -> StatusCode {
+    match error.downcast_ref::<WordError>() {
+        Some(WordError::NotFound(_)) => StatusCode::NOT_FOUND,
+        Some(WordError::Invalid(_)) => StatusCode::BAD_REQUEST,
+        Some(WordError::LimitReached(_)) => StatusCode::TOO_MANY_REQUESTS,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Runs the extraction inline; queue `POST /api/jobs/extract/text` instead.
+#[deprecated]
 #[utoipa::path(
     post,
     path = "/sets/words/extract/text",
     request_body = ExtractWordsFromTextRequest,
     responses(
         (status = 200, description = "Words extracted successfully", body = Vec<ExtractedWord>),
+        (status = 429, description = "LLM quota exceeded"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[instrument(skip(state, request))]
+#[instrument(skip(state, claims, request))]
 async fn extract_words_from_text(
     State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
     Json(request): Json<ExtractWordsFromTextRequest>,
 ) -> Result<axum::Json<Vec<ExtractedWord>>, (StatusCode, String)> {
     info!("Extracting words from text");
     match state
         .set_service
-        .extract_words_from_text(request.text)
+        .extract_words_from_text(&claims.sub, request.text, request.bypass_cache, false, None)
         .await
     {
-        Ok(words) => {
-            info!("Successfully extracted {} words", words.len());
-            Ok(axum::Json(words))
+        Ok(extraction) => {
+            info!("Successfully extracted {} words", extraction.words.len());
+            Ok(axum::Json(extraction.words))
         }
         Err(e) => {
             error!("Failed to extract words: {}", e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            Err((llm_error_status(&e), e.to_string()))
         }
     }
 }
 
+/// Runs the extraction inline; queue `POST /api/jobs/extract/text` with `skip_known` instead.
+#[deprecated]
+#[utoipa::path(
+    post,
+    path = "/sets/words/extract/text/new",
+    request_body = ExtractWordsFromTextRequest,
+    responses(
+        (status = 200, description = "Words of the text the user does not have yet", body = NewWordsResponse),
+        (status = 429, description = "LLM quota exceeded"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn extract_new_words_from_text(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ExtractWordsFromTextRequest>,
+) -> Result<axum::Json<NewWordsResponse>, (StatusCode, String)> {
+    info!("Extracting new words from text");
+    match state
+        .set_service
+        .extract_words_from_text(&claims.sub, request.text, request.bypass_cache, true, None)
+        .await
+    {
+        Ok(extraction) => {
+            info!(
+                "Successfully extracted {} new words, {} known",
+                extraction.words.len(),
+                extraction.known_words.len()
+            );
+            Ok(axum::Json(NewWordsResponse {
+                words: extraction.words,
+                known_words: extraction.known_words,
+            }))
+        }
+        Err(e) => {
+            error!("Failed to extract new words: {}", e);
+            Err((llm_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+/// Runs the extraction inline; queue `POST /api/jobs/extract/image` instead.
+#[deprecated]
 #[utoipa::path(
     post,
     path = "/sets/words/extract/image",
     request_body = ExtractWordsFromImageRequest,
     responses(
         (status = 200, description = "Words extracted successfully", body = Vec<ExtractedWord>),
+        (status = 429, description = "LLM quota exceeded"),
         (status = 500, description = "Internal server error")
     )
 )]
-#[instrument(skip(state, request))]
+#[instrument(skip(state, claims, request))]
 async fn extract_words_from_image(
     State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
     Json(request): Json<ExtractWordsFromImageRequest>,
 ) -> Result<axum::Json<Vec<ExtractedWord>>, (StatusCode, String)> {
     info!("Extracting words from image");
     match state
         .set_service
-        .extract_words_from_image(request.image_data)
+        .extract_words_from_image(&claims.sub, request.image_data, request.bypass_cache, None)
         .await
     {
         Ok(words) => {
@@ -122,6 +294,28 @@ async fn extract_words_from_image(
         }
         Err(e) => {
             error!("Failed to extract words from image: {}", e);
+            Err((llm_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/sets/words/duplicates",
+    responses(
+        (status = 200, description = "Groups of near-duplicate words with the word to keep", body = Vec<MergeProposal>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn find_duplicates(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<Vec<MergeProposal>>, (StatusCode, String)> {
+    match state.set_service.find_duplicates(&claims.sub).await {
+        Ok(proposals) => Ok(axum::Json(proposals)),
+        Err(e) => {
+            error!("Failed to find duplicates: {}", e);
             Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
         }
     }
@@ -171,6 +365,8 @@ async fn save_words(
     ),
     responses(
         (status = 200, description = "Set to next learn stage successfully"),
+        (status = 404, description = "Set not found"),
+        (status = 429, description = "Daily limit of new sets or reviews reached"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -188,7 +384,102 @@ async fn to_next_learn_iter(
         }
         Err(e) => {
             error!("Failed to move set {} to next stage: {}", set_id, e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/sets/{id}/review",
+    params(
+        ("id" = String, Path, description = "Set ID")
+    ),
+    request_body = ReviewSetRequest,
+    responses(
+        (status = 200, description = "Set reviewed successfully"),
+        (status = 404, description = "Set or graded word not found"),
+        (status = 429, description = "Daily limit of new sets or reviews reached"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request), fields(set_id = %set_id))]
+async fn review_set(
+    State(state): State<ApiState>,
+    axum::extract::Path(set_id): axum::extract::Path<String>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ReviewSetRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    info!(
+        "Reviewing set {} with {} grades for user {}",
+        set_id,
+        request.grades.len(),
+        claims.sub
+    );
+    let grades = request
+        .grades
+        .into_iter()
+        .map(WordGrade::into_entry)
+        .collect::<HashMap<_, _>>();
+
+    match state
+        .set_service
+        .review_set(&claims.sub, &set_id, grades)
+        .await
+    {
+        Ok(_) => {
+            info!("Successfully reviewed set {}", set_id);
+            Ok(StatusCode::OK)
+        }
+        Err(e) => {
+            error!("Failed to review set {}: {}", set_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/sets/released/review",
+    request_body = ReviewReleasedRequest,
+    responses(
+        (status = 200, description = "Released words reviewed successfully"),
+        (status = 404, description = "Released word not found"),
+        (status = 429, description = "Daily limit of reviews reached"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn review_released_words(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ReviewReleasedRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    info!(
+        "Reviewing {} released words for user {}",
+        request.grades.len(),
+        claims.sub
+    );
+    let grades = request
+        .grades
+        .into_iter()
+        .map(WordGrade::into_entry)
+        .collect::<HashMap<_, _>>();
+
+    match state.set_service.review_released(&claims.sub, grades).await {
+        Ok(_) => {
+            info!(
+                "Successfully reviewed released words for user {}",
+                claims.sub
+            );
+            Ok(StatusCode::OK)
+        }
+        Err(e) => {
+            error!(
+                "Failed to review released words for user {}: {}",
+                claims.sub, e
+            );
+            Err((word_error_status(&e), e.to_string()))
         }
     }
 }
@@ -231,3 +522,561 @@ async fn mark_as_tobe(
         }
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/sets/export/apkg",
+    responses(
+        (status = 200, description = "Anki package with all sets and released words", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn export_apkg(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    info!("Exporting Anki package for user {}", claims.sub);
+    match state.set_service.export_apkg(&claims.sub).await {
+        Ok(package) => Ok((
+            [
+                (CONTENT_TYPE, "application/octet-stream"),
+                (
+                    CONTENT_DISPOSITION,
+                    "attachment; filename=\"kanji_card.apkg\"",
+                ),
+            ],
+            package,
+        )),
+        Err(e) => {
+            error!(
+                "Failed to export Anki package for user {}: {}",
+                claims.sub, e
+            );
+            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ImportQuery {
+    format: ImportFormat,
+    word: Option<String>,
+    translation: Option<String>,
+    reading: Option<String>,
+    has_header: Option<bool>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/sets/import",
+    params(
+        ("format" = ImportFormat, Query, description = "File format: apkg, csv or tsv"),
+        ("word" = Option<String>, Query, description = "Field name or 1-based column of the word"),
+        ("translation" = Option<String>, Query, description = "Field name or 1-based column of the translation"),
+        ("reading" = Option<String>, Query, description = "Field name or 1-based column of the reading"),
+        ("has_header" = Option<bool>, Query, description = "Whether a delimited file starts with a header row, defaults to true")
+    ),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Words imported into tobe sets", body = ImportReport),
+        (status = 400, description = "Invalid file"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, content))]
+async fn import_words(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<ImportQuery>,
+    content: Bytes,
+) -> Result<Json<ImportReport>, (StatusCode, String)> {
+    info!("Importing words for user {}", claims.sub);
+    let mapping = FieldMapping {
+        word: params.word,
+        translation: params.translation,
+        reading: params.reading,
+        has_header: params.has_header.unwrap_or(true),
+    };
+    match state
+        .set_service
+        .import_words(&claims.sub, params.format, mapping, content.to_vec())
+        .await
+    {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            error!("Failed to import words for user {}: {}", claims.sub, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/sets/{id}/words",
+    params(("id" = String, Path, description = "Set ID")),
+    request_body = ExtractedWord,
+    responses(
+        (status = 201, description = "Word added to the set", body = AddWordResponse),
+        (status = 400, description = "Empty word or the set is started or full"),
+        (status = 404, description = "Set not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request), fields(set_id = %set_id))]
+async fn add_word(
+    State(state): State<ApiState>,
+    axum::extract::Path(set_id): axum::extract::Path<String>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ExtractedWord>,
+) -> Result<(StatusCode, axum::Json<AddWordResponse>), (StatusCode, String)> {
+    match state
+        .set_service
+        .add_word(&claims.sub, &set_id, request)
+        .await
+    {
+        Ok(card) => Ok((
+            StatusCode::CREATED,
+            axum::Json(AddWordResponse {
+                id: card.id().to_owned(),
+            }),
+        )),
+        Err(e) => {
+            error!("Failed to add word to set {}: {}", set_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/sets/{id}/words/{word_id}",
+    params(
+        ("id" = String, Path, description = "Set ID"),
+        ("word_id" = String, Path, description = "Word ID")
+    ),
+    request_body = WordEdit,
+    responses(
+        (status = 200, description = "Word updated"),
+        (status = 400, description = "Word or translation is empty"),
+        (status = 404, description = "Set or word not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn edit_set_word(
+    State(state): State<ApiState>,
+    axum::extract::Path((set_id, word_id)): axum::extract::Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<WordEdit>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state
+        .set_service
+        .edit_set_word(&claims.sub, &set_id, &word_id, request)
+        .await
+    {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) => {
+            error!("Failed to edit word {} in set {}: {}", word_id, set_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sets/{id}/words/{word_id}",
+    params(
+        ("id" = String, Path, description = "Set ID"),
+        ("word_id" = String, Path, description = "Word ID")
+    ),
+    responses(
+        (status = 200, description = "Word removed; an emptied set is removed too"),
+        (status = 404, description = "Set or word not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn delete_set_word(
+    State(state): State<ApiState>,
+    axum::extract::Path((set_id, word_id)): axum::extract::Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state
+        .set_service
+        .delete_set_word(&claims.sub, &set_id, &word_id)
+        .await
+    {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) => {
+            error!(
+                "Failed to delete word {} from set {}: {}",
+                word_id, set_id, e
+            );
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/sets/{id}/words/{word_id}/move",
+    params(
+        ("id" = String, Path, description = "Set ID"),
+        ("word_id" = String, Path, description = "Word ID")
+    ),
+    request_body = MoveWordRequest,
+    responses(
+        (status = 200, description = "Word moved"),
+        (status = 400, description = "A set is started or the target is full"),
+        (status = 404, description = "Set or word not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn move_word(
+    State(state): State<ApiState>,
+    axum::extract::Path((set_id, word_id)): axum::extract::Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<MoveWordRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state
+        .set_service
+        .move_word(&claims.sub, &set_id, &word_id, &request.target_set_id)
+        .await
+    {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) => {
+            error!("Failed to move word {} from set {}: {}", word_id, set_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/sets/released/words/{word_id}",
+    params(("word_id" = String, Path, description = "Word ID")),
+    request_body = WordEdit,
+    responses(
+        (status = 200, description = "Released word updated"),
+        (status = 400, description = "Word or translation is empty"),
+        (status = 404, description = "Word not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn edit_released_word(
+    State(state): State<ApiState>,
+    axum::extract::Path(word_id): axum::extract::Path<String>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<WordEdit>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state
+        .set_service
+        .edit_released_word(&claims.sub, &word_id, request)
+        .await
+    {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) => {
+            error!("Failed to edit released word {}: {}", word_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sets/released/words/{word_id}",
+    params(("word_id" = String, Path, description = "Word ID")),
+    responses(
+        (status = 200, description = "Released word removed"),
+        (status = 404, description = "Word not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn delete_released_word(
+    State(state): State<ApiState>,
+    axum::extract::Path(word_id): axum::extract::Path<String>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state
+        .set_service
+        .delete_released_word(&claims.sub, &word_id)
+        .await
+    {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) => {
+            error!("Failed to delete released word {}: {}", word_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/sets",
+    request_body = CreateSetRequest,
+    responses(
+        (status = 201, description = "Empty named set created", body = CreatedSetResponse),
+        (status = 400, description = "Name is empty"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn create_set(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<CreateSetRequest>,
+) -> Result<(StatusCode, axum::Json<CreatedSetResponse>), (StatusCode, String)> {
+    match state
+        .set_service
+        .create_set(&claims.sub, request.name)
+        .await
+    {
+        Ok(set) => Ok((
+            StatusCode::CREATED,
+            axum::Json(CreatedSetResponse {
+                id: set.id().to_owned(),
+            }),
+        )),
+        Err(e) => {
+            error!("Failed to create set for user {}: {}", claims.sub, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sets/{id}",
+    params(("id" = String, Path, description = "Set ID")),
+    responses(
+        (status = 200, description = "Set and its words removed"),
+        (status = 404, description = "Set not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims), fields(set_id = %set_id))]
+async fn delete_set(
+    State(state): State<ApiState>,
+    axum::extract::Path(set_id): axum::extract::Path<String>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state.set_service.delete_set(&claims.sub, &set_id).await {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) => {
+            error!("Failed to delete set {}: {}", set_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/sets/{id}/merge",
+    params(("id" = String, Path, description = "Set ID receiving the words")),
+    request_body = MergeSetsRequest,
+    responses(
+        (status = 200, description = "Sets merged"),
+        (status = 400, description = "A set is started or the result is too large"),
+        (status = 404, description = "Set not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request), fields(set_id = %set_id))]
+async fn merge_sets(
+    State(state): State<ApiState>,
+    axum::extract::Path(set_id): axum::extract::Path<String>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<MergeSetsRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state
+        .set_service
+        .merge_sets(&claims.sub, &set_id, &request.source_set_id)
+        .await
+    {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) => {
+            error!("Failed to merge into set {}: {}", set_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/sets/{id}/split",
+    params(("id" = String, Path, description = "Set ID")),
+    request_body = SplitSetRequest,
+    responses(
+        (status = 201, description = "New set with the chosen words", body = CreatedSetResponse),
+        (status = 400, description = "Set is started or a set would be empty"),
+        (status = 404, description = "Set not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request), fields(set_id = %set_id))]
+async fn split_set(
+    State(state): State<ApiState>,
+    axum::extract::Path(set_id): axum::extract::Path<String>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<SplitSetRequest>,
+) -> Result<(StatusCode, axum::Json<CreatedSetResponse>), (StatusCode, String)> {
+    match state
+        .set_service
+        .split_set(&claims.sub, &set_id, request.word_ids, request.name)
+        .await
+    {
+        Ok(set) => Ok((
+            StatusCode::CREATED,
+            axum::Json(CreatedSetResponse {
+                id: set.id().to_owned(),
+            }),
+        )),
+        Err(e) => {
+            error!("Failed to split set {}: {}", set_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/sets/{id}/order",
+    params(("id" = String, Path, description = "Set ID")),
+    request_body = ReorderSetRequest,
+    responses(
+        (status = 200, description = "Words reordered"),
+        (status = 400, description = "Ids do not match the words of the set"),
+        (status = 404, description = "Set not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request), fields(set_id = %set_id))]
+async fn reorder_set(
+    State(state): State<ApiState>,
+    axum::extract::Path(set_id): axum::extract::Path<String>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<ReorderSetRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state
+        .set_service
+        .reorder_set(&claims.sub, &set_id, request.word_ids)
+        .await
+    {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) => {
+            error!("Failed to reorder set {}: {}", set_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/sets/{id}/start",
+    params(("id" = String, Path, description = "Set ID")),
+    responses(
+        (status = 200, description = "Set started, first review due after the first interval of the ladder"),
+        (status = 400, description = "Set is already started or empty"),
+        (status = 404, description = "Set not found"),
+        (status = 429, description = "Daily limit of new sets reached"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims), fields(set_id = %set_id))]
+async fn start_set(
+    State(state): State<ApiState>,
+    axum::extract::Path(set_id): axum::extract::Path<String>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state.set_service.start_set(&claims.sub, &set_id).await {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) => {
+            error!("Failed to start set {}: {}", set_id, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/settings",
+    responses(
+        (status = 200, description = "Learning settings of the user, defaults if never changed", body = LearningSettings),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn get_learning_settings(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<LearningSettings>, (StatusCode, String)> {
+    match state.set_service.learning_settings(&claims.sub).await {
+        Ok(settings) => Ok(Json(settings)),
+        Err(e) => {
+            error!("Failed to load learning settings of {}: {}", claims.sub, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/settings",
+    request_body = LearningSettings,
+    responses(
+        (status = 200, description = "Learning settings saved", body = LearningSettings),
+        (status = 400, description = "Invalid settings"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims, request))]
+async fn update_learning_settings(
+    State(state): State<ApiState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<LearningSettings>,
+) -> Result<Json<LearningSettings>, (StatusCode, String)> {
+    match state
+        .set_service
+        .update_learning_settings(&claims.sub, request)
+        .await
+    {
+        Ok(settings) => Ok(Json(settings)),
+        Err(e) => {
+            error!("Failed to save learning settings of {}: {}", claims.sub, e);
+            Err((word_error_status(&e), e.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_errors_map_to_their_status() {
+        let status = |error: WordError| word_error_status(&error.into());
+
+        assert_eq!(
+            status(WordError::NotFound(
+                "Released word ../x not found".to_owned()
+            )),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(WordError::Invalid(String::new())),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(WordError::LimitReached(String::new())),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            word_error_status(&anyhow::anyhow!("disk full")),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
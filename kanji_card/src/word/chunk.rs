@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use crate::{
+    dictionary::morphology::TextSegment,
+    llm::ExtractedWord,
+    word::duplicate::{self, DuplicateIndex},
+};
+
+const SENTENCE_ENDS: [char; 5] = ['。', '！', '？', '\n', '\r'];
+
+/// Splits text after sentence ends and newlines, dropping blank pieces.
+fn split_sentences(text: &str) -> Vec<&str> {
+    text.split_inclusive(SENTENCE_ENDS)
+        .filter(|x| !x.trim().is_empty())
+        .collect()
+}
+
+/// Groups whole sentences into chunks of at most `max_chars` characters.
+/// A sentence longer than that is cut into pieces of `max_chars`.
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+
+    for sentence in split_sentences(text) {
+        let sentence_chars = sentence.chars().count();
+        if current_chars + sentence_chars > max_chars && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        if sentence_chars > max_chars {
+            let chars = sentence.chars().collect::<Vec<_>>();
+            chunks.extend(chars.chunks(max_chars).map(|x| x.iter().collect()));
+            continue;
+        }
+        current.push_str(sentence);
+        current_chars += sentence_chars;
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Cuts the words found in `known` out of a segmented chunk, leaving a
+/// space in their place. Returns the remaining text, `None` when only
+/// punctuation is left, and the dictionary forms of the words cut out.
+pub fn drop_known_words(
+    segments: Vec<TextSegment>,
+    known: &DuplicateIndex,
+) -> (Option<String>, Vec<String>) {
+    let mut remainder = String::new();
+    let mut skipped = vec![];
+    for segment in segments {
+        match segment.lemma {
+            Some(lemma) if known.contains(&lemma.base_form, lemma.reading.as_deref()) => {
+                if !remainder.is_empty() && !remainder.ends_with(char::is_whitespace) {
+                    remainder.push(' ');
+                }
+                if !skipped.contains(&lemma.base_form) {
+                    skipped.push(lemma.base_form);
+                }
+            }
+            _ => remainder.push_str(&segment.surface),
+        }
+    }
+    let remainder = remainder
+        .chars()
+        .any(char::is_alphanumeric)
+        .then(|| remainder.trim().to_owned());
+    (remainder, skipped)
+}
+
+/// Merges words of several chunks, keeping the first occurrence of every
+/// word and completing its reading from later ones.
+pub fn merge_words(chunks: Vec<Vec<ExtractedWord>>) -> Vec<ExtractedWord> {
+    let mut words: Vec<ExtractedWord> = Vec::new();
+    let mut positions = HashMap::new();
+    for word in chunks.into_iter().flatten() {
+        let key = duplicate::normalize(&word.word);
+        match positions.get(&key) {
+            Some(&position) => {
+                let existing: &mut ExtractedWord = &mut words[position];
+                if existing.reading.is_none() {
+                    existing.reading = word.reading;
+                }
+            }
+            None => {
+                positions.insert(key, words.len());
+                words.push(word);
+            }
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary::morphology::Lemma;
+
+    fn word(word: &str, reading: Option<&str>) -> ExtractedWord {
+        ExtractedWord {
+            word: word.to_owned(),
+            translation: "t".to_owned(),
+            reading: reading.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn sentences_are_kept_whole() {
+        let chunks = chunk_text("猫が好き。犬も好き。鳥は？", 10);
+
+        assert_eq!(chunks, vec!["猫が好き。犬も好き。", "鳥は？"]);
+    }
+
+    #[test]
+    fn sentence_longer_than_a_chunk_is_cut() {
+        let chunks = chunk_text("短い。とても長い文です。", 4);
+
+        assert_eq!(chunks, vec!["短い。", "とても長", "い文です", "。"]);
+    }
+
+    #[test]
+    fn blank_lines_are_dropped() {
+        let chunks = chunk_text("\n\n猫。\n  \n", 100);
+
+        assert_eq!(chunks, vec!["猫。"]);
+    }
+
+    fn segment(surface: &str, base_form: Option<&str>) -> TextSegment {
+        TextSegment {
+            surface: surface.to_owned(),
+            lemma: base_form.map(|x| Lemma {
+                base_form: x.to_owned(),
+                reading: None,
+                part_of_speech: "名詞".to_owned(),
+            }),
+        }
+    }
+
+    #[test]
+    fn known_words_are_cut_out() {
+        let mut known = DuplicateIndex::default();
+        known.insert("猫", Some("ねこ"));
+        known.insert("犬", Some("いぬ"));
+        let segments = vec![
+            segment("猫が", Some("猫")),
+            segment("鳥を", Some("鳥")),
+            segment("見た", Some("見る")),
+            segment("。", None),
+            segment("犬", Some("犬")),
+            segment("猫", Some("猫")),
+            segment("好き", Some("好き")),
+        ];
+
+        let (remainder, skipped) = drop_known_words(segments, &known);
+
+        assert_eq!(remainder.as_deref(), Some("鳥を見た。 好き"));
+        assert_eq!(skipped, ["猫", "犬"]);
+    }
+
+    #[test]
+    fn nothing_is_left_when_every_word_is_known() {
+        let mut known = DuplicateIndex::default();
+        known.insert("猫", None);
+        let segments = vec![segment("猫が", Some("猫")), segment("。", None)];
+
+        let (remainder, skipped) = drop_known_words(segments, &known);
+
+        assert_eq!(remainder, None);
+        assert_eq!(skipped, ["猫"]);
+    }
+
+    #[test]
+    fn merge_keeps_first_word_and_fills_reading() {
+        let words = merge_words(vec![
+            vec![word("猫", None), word("犬", Some("いぬ"))],
+            vec![word("猫", Some("ねこ")), word("犬", Some("けん"))],
+        ]);
+
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].reading.as_deref(), Some("ねこ"));
+        assert_eq!(words[1].reading.as_deref(), Some("いぬ"));
+    }
+}
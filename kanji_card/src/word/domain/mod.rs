@@ -1,8 +1,30 @@
-use chrono::{DateTime, Utc};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Days, Utc};
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
+use utoipa::ToSchema;
 
+use crate::{
+    dictionary::kanji::is_kanji,
+    word::domain::schedule::{CardSchedule, Rating, Scheduler},
+};
+
+pub mod review;
+pub mod schedule;
 pub mod set;
+pub mod settings;
+
+/// Interval assumed for words released before per-card scheduling existed.
+const LEGACY_RELEASE_INTERVAL_DAYS: u64 = 30;
+
+/// Changes to a card; absent fields stay as they are and an empty reading
+/// clears it.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct WordEdit {
+    pub word: Option<String>,
+    pub translation: Option<String>,
+    pub reading: Option<String>,
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct WordCard {
@@ -10,25 +32,66 @@ pub struct WordCard {
 
     word: String,
     translation: String,
+    #[serde(default)]
+    reading: Option<String>,
+    #[serde(default)]
+    part_of_speech: Option<String>,
 
     release_timestamp: Option<DateTime<Utc>>,
+
+    #[serde(default)]
+    schedule: Option<CardSchedule>,
 }
 
 impl WordCard {
-    pub fn new(word: String, translation: String) -> Self {
+    pub fn new(
+        word: String,
+        translation: String,
+        reading: Option<String>,
+        part_of_speech: Option<String>,
+    ) -> Self {
         let word = word.trim().to_owned();
         let translation = translation.trim().to_owned();
+        let reading = reading
+            .map(|x| x.trim().to_owned())
+            .filter(|x| !x.is_empty());
 
         Self {
             id: Ulid::new().to_string(),
             word,
             translation,
+            reading,
+            part_of_speech,
             release_timestamp: None,
+            schedule: None,
         }
     }
 
+    /// Dictionary reading when known, otherwise a kakasi transliteration.
     pub fn reading(&self) -> String {
-        kakasi::convert(&self.word).hiragana
+        match &self.reading {
+            Some(reading) => reading.clone(),
+            None => kakasi::convert(&self.word).hiragana,
+        }
+    }
+
+    /// Distinct kanji of the word in order of appearance.
+    pub fn kanji(&self) -> Vec<char> {
+        let mut result = vec![];
+        for ch in self.word.chars().filter(|x| is_kanji(*x)) {
+            if !result.contains(&ch) {
+                result.push(ch);
+            }
+        }
+        result
+    }
+
+    pub fn part_of_speech(&self) -> Option<&str> {
+        self.part_of_speech.as_deref()
+    }
+
+    pub fn known_reading(&self) -> Option<&str> {
+        self.reading.as_deref()
     }
 
     pub fn id(&self) -> &str {
@@ -46,4 +109,57 @@ impl WordCard {
     pub fn release_timestamp(&self) -> Option<chrono::DateTime<chrono::Utc>> {
         self.release_timestamp
     }
+
+    pub fn schedule(&self) -> Option<&CardSchedule> {
+        self.schedule.as_ref()
+    }
+
+    pub fn due(&self) -> Option<DateTime<Utc>> {
+        match (&self.schedule, self.release_timestamp) {
+            (Some(schedule), _) => Some(schedule.due()),
+            (None, Some(release)) => {
+                release.checked_add_days(Days::new(LEGACY_RELEASE_INTERVAL_DAYS))
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// Applies the edit; the word and the translation cannot become empty.
+    pub fn edit(&mut self, edit: WordEdit) -> Result<()> {
+        let word = edit.word.map(|x| x.trim().to_owned());
+        let translation = edit.translation.map(|x| x.trim().to_owned());
+        if word.as_ref().is_some_and(|x| x.is_empty()) {
+            return Err(anyhow!("Word cannot be empty"));
+        }
+        if translation.as_ref().is_some_and(|x| x.is_empty()) {
+            return Err(anyhow!("Translation cannot be empty"));
+        }
+
+        if let Some(word) = word {
+            if word != self.word {
+                self.part_of_speech = None;
+            }
+            self.word = word;
+        }
+        if let Some(translation) = translation {
+            self.translation = translation;
+        }
+        if let Some(reading) = edit.reading {
+            self.reading = Some(reading.trim().to_owned()).filter(|x| !x.is_empty());
+        }
+        Ok(())
+    }
+
+    /// Takes a released card back into learning; its schedule is kept.
+    pub fn unrelease(&mut self) {
+        self.release_timestamp = None;
+    }
+
+    pub fn review(&mut self, scheduler: &Scheduler, rating: Rating, now: DateTime<Utc>) {
+        let schedule = self.schedule.clone().or_else(|| {
+            self.release_timestamp
+                .map(|x| scheduler.seed(LEGACY_RELEASE_INTERVAL_DAYS as f64, x))
+        });
+        self.schedule = Some(scheduler.review(schedule.as_ref(), rating, now));
+    }
 }
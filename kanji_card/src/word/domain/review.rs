@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::word::domain::{schedule::Rating, set::LearnSetState};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReviewGrade {
+    pub rating: Rating,
+    pub response_time_ms: Option<u64>,
+}
+
+impl ReviewGrade {
+    pub fn new(rating: Rating) -> Self {
+        Self {
+            rating,
+            response_time_ms: None,
+        }
+    }
+}
+
+/// One graded answer for a card. `None` states mean the card lives in the
+/// release store rather than in a set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewLogEntry {
+    id: String,
+    card_id: String,
+    word: String,
+    set_id: Option<String>,
+    timestamp: DateTime<Utc>,
+    rating: Rating,
+    response_time_ms: Option<u64>,
+    state_before: Option<LearnSetState>,
+    state_after: Option<LearnSetState>,
+}
+
+impl ReviewLogEntry {
+    pub fn new(
+        card_id: String,
+        word: String,
+        set_id: Option<String>,
+        timestamp: DateTime<Utc>,
+        grade: ReviewGrade,
+        state_before: Option<LearnSetState>,
+        state_after: Option<LearnSetState>,
+    ) -> Self {
+        Self {
+            id: Ulid::new().to_string(),
+            card_id,
+            word,
+            set_id,
+            timestamp,
+            rating: grade.rating,
+            response_time_ms: grade.response_time_ms,
+            state_before,
+            state_after,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn card_id(&self) -> &str {
+        &self.card_id
+    }
+
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+
+    pub fn set_id(&self) -> Option<&str> {
+        self.set_id.as_deref()
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    pub fn rating(&self) -> Rating {
+        self.rating
+    }
+
+    pub fn response_time_ms(&self) -> Option<u64> {
+        self.response_time_ms
+    }
+
+    pub fn state_before(&self) -> Option<&LearnSetState> {
+        self.state_before.as_ref()
+    }
+
+    pub fn state_after(&self) -> Option<&LearnSetState> {
+        self.state_after.as_ref()
+    }
+}
@@ -0,0 +1,305 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Default FSRS-4.5 model weights.
+const DEFAULT_WEIGHTS: [f64; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+
+const DECAY: f64 = -0.5;
+const FACTOR: f64 = 19.0 / 81.0;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, Serialize, Deserialize, ToSchema)]
+pub enum Rating {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Rating {
+    fn value(self) -> f64 {
+        match self {
+            Rating::Again => 1.0,
+            Rating::Hard => 2.0,
+            Rating::Good => 3.0,
+            Rating::Easy => 4.0,
+        }
+    }
+}
+
+/// Memory state of a single card as tracked by the scheduler.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CardSchedule {
+    stability: f64,
+    difficulty: f64,
+    due: DateTime<Utc>,
+    last_review: DateTime<Utc>,
+    reps: u32,
+    lapses: u32,
+}
+
+impl CardSchedule {
+    pub fn due(&self) -> DateTime<Utc> {
+        self.due
+    }
+
+    /// Days between the last review and the due date.
+    pub fn interval_days(&self) -> i64 {
+        (self.due - self.last_review).num_days()
+    }
+}
+
+/// FSRS scheduler: computes the next memory state of a card from a rating.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    weights: [f64; 17],
+    desired_retention: f64,
+    maximum_interval: i64,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            weights: DEFAULT_WEIGHTS,
+            desired_retention: 0.9,
+            maximum_interval: 36500,
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn with_desired_retention(mut self, desired_retention: f64) -> Self {
+        self.desired_retention = desired_retention;
+        self
+    }
+
+    /// Builds a schedule for a card that was learned outside of the scheduler.
+    pub fn seed(&self, stability: f64, last_review: DateTime<Utc>) -> CardSchedule {
+        CardSchedule {
+            stability,
+            difficulty: self.initial_difficulty(Rating::Good),
+            due: last_review + Duration::days(self.next_interval(stability)),
+            last_review,
+            reps: 0,
+            lapses: 0,
+        }
+    }
+
+    pub fn review(
+        &self,
+        schedule: Option<&CardSchedule>,
+        rating: Rating,
+        now: DateTime<Utc>,
+    ) -> CardSchedule {
+        let (stability, difficulty, reps, lapses) = match schedule {
+            None => (
+                self.initial_stability(rating),
+                self.initial_difficulty(rating),
+                0,
+                0,
+            ),
+            Some(schedule) => {
+                let elapsed_days =
+                    ((now - schedule.last_review).num_seconds() as f64 / 86400.0).max(0.0);
+                let retrievability = self.retrievability(elapsed_days, schedule.stability);
+
+                let stability = match rating {
+                    Rating::Again => self.forget_stability(
+                        schedule.difficulty,
+                        schedule.stability,
+                        retrievability,
+                    ),
+                    _ => self.recall_stability(
+                        schedule.difficulty,
+                        schedule.stability,
+                        retrievability,
+                        rating,
+                    ),
+                };
+
+                (
+                    stability,
+                    self.next_difficulty(schedule.difficulty, rating),
+                    schedule.reps,
+                    schedule.lapses,
+                )
+            }
+        };
+
+        let interval_days = match rating {
+            // Forgotten cards go straight back into the current session.
+            Rating::Again => 0,
+            _ => self.next_interval(stability),
+        };
+
+        CardSchedule {
+            stability,
+            difficulty,
+            due: now + Duration::days(interval_days),
+            last_review: now,
+            reps: reps + 1,
+            lapses: if rating == Rating::Again && schedule.is_some() {
+                lapses + 1
+            } else {
+                lapses
+            },
+        }
+    }
+
+    fn retrievability(&self, elapsed_days: f64, stability: f64) -> f64 {
+        (1.0 + FACTOR * elapsed_days / stability).powf(DECAY)
+    }
+
+    fn next_interval(&self, stability: f64) -> i64 {
+        let interval = stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0);
+        (interval.round() as i64).clamp(1, self.maximum_interval)
+    }
+
+    fn initial_stability(&self, rating: Rating) -> f64 {
+        self.weights[rating.value() as usize - 1].max(0.1)
+    }
+
+    fn initial_difficulty(&self, rating: Rating) -> f64 {
+        (self.weights[4] - (rating.value() - 3.0) * self.weights[5]).clamp(1.0, 10.0)
+    }
+
+    fn next_difficulty(&self, difficulty: f64, rating: Rating) -> f64 {
+        let next = difficulty - self.weights[6] * (rating.value() - 3.0);
+        let reverted = self.weights[7] * self.initial_difficulty(Rating::Good)
+            + (1.0 - self.weights[7]) * next;
+        reverted.clamp(1.0, 10.0)
+    }
+
+    fn recall_stability(
+        &self,
+        difficulty: f64,
+        stability: f64,
+        retrievability: f64,
+        rating: Rating,
+    ) -> f64 {
+        let hard_penalty = if rating == Rating::Hard {
+            self.weights[15]
+        } else {
+            1.0
+        };
+        let easy_bonus = if rating == Rating::Easy {
+            self.weights[16]
+        } else {
+            1.0
+        };
+
+        stability
+            * (self.weights[8].exp()
+                * (11.0 - difficulty)
+                * stability.powf(-self.weights[9])
+                * ((self.weights[10] * (1.0 - retrievability)).exp() - 1.0)
+                * hard_penalty
+                * easy_bonus
+                + 1.0)
+    }
+
+    fn forget_stability(&self, difficulty: f64, stability: f64, retrievability: f64) -> f64 {
+        let next = self.weights[11]
+            * difficulty.powf(-self.weights[12])
+            * ((stability + 1.0).powf(self.weights[13]) - 1.0)
+            * (self.weights[14] * (1.0 - retrievability)).exp();
+        next.min(stability).max(0.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn first_review_stability_follows_the_grade() {
+        let scheduler = Scheduler::default();
+        let stabilities = [Rating::Again, Rating::Hard, Rating::Good, Rating::Easy]
+            .map(|rating| scheduler.review(None, rating, now()).stability);
+
+        assert_eq!(stabilities, [0.4872, 1.4003, 3.7145, 13.8206]);
+    }
+
+    #[test]
+    fn first_review_schedules_new_cards() {
+        let scheduler = Scheduler::default();
+        let again = scheduler.review(None, Rating::Again, now());
+        let good = scheduler.review(None, Rating::Good, now());
+
+        assert_eq!(again.due, now());
+        assert_eq!(again.lapses, 0);
+        assert_eq!(good.interval_days(), 4);
+        assert_eq!(good.reps, 1);
+    }
+
+    #[test]
+    fn interval_equals_stability_at_ninety_percent_retention() {
+        let scheduler = Scheduler::default();
+
+        assert_eq!(scheduler.next_interval(1.0), 1);
+        assert_eq!(scheduler.next_interval(10.0), 10);
+        assert_eq!(scheduler.next_interval(100.0), 100);
+    }
+
+    #[test]
+    fn lower_retention_gives_longer_intervals() {
+        let strict = Scheduler::default().with_desired_retention(0.95);
+        let relaxed = Scheduler::default().with_desired_retention(0.8);
+
+        assert!(strict.next_interval(10.0) < 10);
+        assert!(relaxed.next_interval(10.0) > 10);
+    }
+
+    #[test]
+    fn intervals_grow_with_recalls_on_time() {
+        let scheduler = Scheduler::default();
+        let mut schedule = scheduler.review(None, Rating::Good, now());
+        let mut intervals = vec![schedule.interval_days()];
+        for _ in 0..4 {
+            schedule = scheduler.review(Some(&schedule), Rating::Good, schedule.due);
+            intervals.push(schedule.interval_days());
+        }
+
+        assert!(intervals.windows(2).all(|x| x[0] < x[1]), "{intervals:?}");
+    }
+
+    #[test]
+    fn lapse_resets_the_card_into_the_session() {
+        let scheduler = Scheduler::default();
+        let learned = scheduler.review(None, Rating::Easy, now());
+        let forgotten = scheduler.review(Some(&learned), Rating::Again, learned.due);
+
+        assert_eq!(forgotten.due, learned.due);
+        assert_eq!(forgotten.lapses, 1);
+        assert_eq!(forgotten.reps, 2);
+        assert!(forgotten.stability < learned.stability);
+        assert!(forgotten.difficulty > learned.difficulty);
+    }
+
+    #[test]
+    fn difficulty_stays_within_bounds() {
+        let scheduler = Scheduler::default();
+        let limits = [Rating::Again, Rating::Easy].map(|rating| {
+            let mut schedule = scheduler.review(None, rating, now());
+            for _ in 0..50 {
+                schedule = scheduler.review(Some(&schedule), rating, schedule.due);
+                assert!((1.0..=10.0).contains(&schedule.difficulty));
+            }
+            schedule.difficulty
+        });
+
+        assert_eq!(limits, [10.0, 1.0]);
+
+        assert!((scheduler.initial_difficulty(Rating::Again) - 7.6214).abs() < 1e-9);
+        assert!((scheduler.initial_difficulty(Rating::Easy) - 3.932).abs() < 1e-9);
+    }
+}
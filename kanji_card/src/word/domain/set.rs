@@ -1,17 +1,28 @@
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, Days, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use ulid::Ulid;
 use utoipa::ToSchema;
 
-use crate::word::domain::WordCard;
+use crate::word::domain::{
+    WordCard,
+    schedule::{Rating, Scheduler},
+    settings::{LADDER_LEN, LearningSettings},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LearnSet {
     id: String,
+    /// Given to sets curated by hand; unnamed sets collect saved words.
+    #[serde(default)]
+    name: Option<String>,
     state: LearnSetState,
     words: Vec<WordCard>,
     state_timestamp: Option<DateTime<Utc>>,
+    /// When the set left `Tobe`, for the daily limit of new sets.
+    #[serde(default)]
+    started_at: Option<DateTime<Utc>>,
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -26,15 +37,50 @@ pub enum LearnSetState {
     TenDay,
 }
 
-const MAX_SET_LEN: usize = 8;
+/// Learning stages in the order of the interval ladder.
+const LADDER_STATES: [LearnSetState; LADDER_LEN] = [
+    LearnSetState::OneDay,
+    LearnSetState::TwoDay,
+    LearnSetState::ThreeDay,
+    LearnSetState::FiveDay,
+    LearnSetState::SevenDay,
+    LearnSetState::TenDay,
+];
+
+impl LearnSetState {
+    /// Maps a scheduled interval onto the last stage of the ladder it reaches.
+    pub fn from_interval_days(days: i64, ladder: &[u32]) -> Self {
+        let stage = ladder
+            .iter()
+            .take_while(|x| i64::from(**x) <= days)
+            .count()
+            .clamp(1, LADDER_STATES.len());
+        LADDER_STATES[stage - 1].clone()
+    }
+
+    /// Days until the review of a set in this stage; `None` for `Tobe`.
+    fn ladder_days(&self, ladder: &[u32]) -> Option<u32> {
+        let stage = LADDER_STATES.iter().position(|x| x == self)?;
+        ladder.get(stage).copied()
+    }
+}
 
 impl LearnSet {
     pub fn new() -> Self {
         Self {
             id: Ulid::new().to_string(),
+            name: None,
             state: LearnSetState::Tobe,
             words: vec![],
             state_timestamp: None,
+            started_at: None,
+        }
+    }
+
+    pub fn named(name: String) -> Self {
+        Self {
+            name: Some(name.trim().to_owned()).filter(|x| !x.is_empty()),
+            ..Self::new()
         }
     }
 
@@ -42,20 +88,30 @@ impl LearnSet {
         &self.id
     }
 
-    pub fn time_to_learn(&self) -> Option<DateTime<Utc>> {
-        self.state_timestamp.map(|x| match &self.state {
-            LearnSetState::Tobe => x,
-            LearnSetState::OneDay => x.checked_add_days(Days::new(1)).unwrap_or_default(),
-            LearnSetState::TwoDay => x.checked_add_days(Days::new(2)).unwrap_or_default(),
-            LearnSetState::ThreeDay => x.checked_add_days(Days::new(3)).unwrap_or_default(),
-            LearnSetState::FiveDay => x.checked_add_days(Days::new(5)).unwrap_or_default(),
-            LearnSetState::SevenDay => x.checked_add_days(Days::new(7)).unwrap_or_default(),
-            LearnSetState::TenDay => x.checked_add_days(Days::new(10)).unwrap_or_default(),
-        })
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
     }
 
-    pub fn need_to_learn(&self) -> bool {
-        let time_to_learn = self.time_to_learn();
+    pub fn time_to_learn(&self, settings: &LearningSettings) -> Option<DateTime<Utc>> {
+        if self.state != LearnSetState::Tobe
+            && let Some(due) = self.words.iter().filter_map(|x| x.due()).min()
+        {
+            return Some(due);
+        }
+
+        // Sets started before per-card scheduling fall back to the ladder.
+        self.state_timestamp.map(
+            |x| match self.state.ladder_days(&settings.interval_ladder) {
+                Some(days) => x
+                    .checked_add_days(Days::new(days.into()))
+                    .unwrap_or_default(),
+                None => x,
+            },
+        )
+    }
+
+    pub fn need_to_learn(&self, settings: &LearningSettings) -> bool {
+        let time_to_learn = self.time_to_learn(settings);
         match time_to_learn {
             Some(x) => x <= Utc::now(),
             None => false,
@@ -70,40 +126,148 @@ impl LearnSet {
         &self.words
     }
 
-    pub fn iter(&mut self) -> Option<Vec<WordCard>> {
-        self.state_timestamp = Some(Utc::now());
-        self.state = match &self.state {
-            LearnSetState::Tobe => LearnSetState::OneDay,
-            LearnSetState::OneDay => LearnSetState::TwoDay,
-            LearnSetState::TwoDay => LearnSetState::ThreeDay,
-            LearnSetState::ThreeDay => LearnSetState::FiveDay,
-            LearnSetState::FiveDay => LearnSetState::SevenDay,
-            LearnSetState::SevenDay | LearnSetState::TenDay => LearnSetState::TenDay,
-        };
+    /// Reviews the set with a grade per word and returns the cards whose
+    /// interval has grown long enough to leave the set. Words without a grade
+    /// count as recalled; forgotten words stay and pull the set state back.
+    pub fn review(
+        &mut self,
+        scheduler: &Scheduler,
+        settings: &LearningSettings,
+        ratings: &HashMap<String, Rating>,
+    ) -> Result<Vec<WordCard>> {
+        if let Some(id) = ratings
+            .keys()
+            .find(|id| !self.words.iter().any(|word| word.id() == id.as_str()))
+        {
+            return Err(anyhow!("Word {id} not found in set"));
+        }
+
+        let now = Utc::now();
+        for word in self.words.iter_mut() {
+            let rating = ratings.get(word.id()).copied().unwrap_or(Rating::Good);
+            word.review(scheduler, rating, now);
+        }
 
-        if self.state == LearnSetState::TenDay {
-            let mut words = vec![];
-            for word in self.words.iter() {
-                let mut word = word.clone();
-                word.release_timestamp = Some(Utc::now());
-                words.push(word);
-            }
-            Some(words)
-        } else {
-            None
+        self.state_timestamp = Some(now);
+        if self.state == LearnSetState::Tobe {
+            self.started_at = Some(now);
         }
+        Ok(self.release_learned(settings, now))
     }
 
-    pub fn is_writabe(&self) -> bool {
-        self.words.len() < MAX_SET_LEN && self.state == LearnSetState::Tobe
+    fn release_learned(
+        &mut self,
+        settings: &LearningSettings,
+        now: DateTime<Utc>,
+    ) -> Vec<WordCard> {
+        let release_days = settings.release_interval_days();
+        let (mut released, learning): (Vec<_>, Vec<_>) = self.words.drain(..).partition(|word| {
+            word.schedule()
+                .is_some_and(|x| x.interval_days() >= release_days)
+        });
+
+        self.words = learning;
+        self.state = self
+            .words
+            .iter()
+            .filter_map(|x| x.schedule().map(|x| x.interval_days()))
+            .min()
+            .map(|x| LearnSetState::from_interval_days(x, &settings.interval_ladder))
+            .unwrap_or(LearnSetState::TenDay);
+
+        for word in released.iter_mut() {
+            word.release_timestamp = Some(now);
+        }
+        released
+    }
+
+    pub fn is_writabe(&self, settings: &LearningSettings) -> bool {
+        self.words.len() < settings.set_size && self.state == LearnSetState::Tobe
     }
 
-    pub fn push(&mut self, word: String, translation: String) -> Result<()> {
-        if !self.is_writabe() {
+    /// Adds an existing card; only sets not started yet with room accept it.
+    pub fn insert(&mut self, card: WordCard, settings: &LearningSettings) -> Result<()> {
+        if !self.is_writabe(settings) {
             return Err(anyhow!("Set is not writable"));
         }
 
-        self.words.push(WordCard::new(word, translation));
+        self.words.push(card);
+        Ok(())
+    }
+
+    pub fn word_mut(&mut self, id: &str) -> Option<&mut WordCard> {
+        self.words.iter_mut().find(|x| x.id() == id)
+    }
+
+    pub fn remove_word(&mut self, id: &str) -> Option<WordCard> {
+        let position = self.words.iter().position(|x| x.id() == id)?;
+        Some(self.words.remove(position))
+    }
+
+    /// Appends the words of another set that has not been started either.
+    pub fn merge(&mut self, other: LearnSet, settings: &LearningSettings) -> Result<()> {
+        if self.state != LearnSetState::Tobe || other.state != LearnSetState::Tobe {
+            return Err(anyhow!("Only sets that are not started can be merged"));
+        }
+        if self.words.len() + other.words.len() > settings.set_size {
+            return Err(anyhow!("A set holds at most {} words", settings.set_size));
+        }
+        self.words.extend(other.words);
+        Ok(())
+    }
+
+    /// Moves the given words into a new set that has not been started.
+    pub fn split(&mut self, word_ids: &[String], name: Option<String>) -> Result<LearnSet> {
+        if self.state != LearnSetState::Tobe {
+            return Err(anyhow!("Only sets that are not started can be split"));
+        }
+        if let Some(id) = word_ids
+            .iter()
+            .find(|id| self.words.iter().all(|x| x.id() != *id))
+        {
+            return Err(anyhow!("Word {id} not found in set"));
+        }
+        if word_ids.is_empty() || self.words.iter().all(|x| word_ids.contains(&x.id)) {
+            return Err(anyhow!("Both sets must keep at least one word"));
+        }
+
+        let mut other = LearnSet::named(name.unwrap_or_default());
+        let (moved, kept) = self.words.drain(..).partition(|x| word_ids.contains(&x.id));
+        self.words = kept;
+        other.words = moved;
+        Ok(other)
+    }
+
+    /// Puts the words in the given order; every word must be listed once.
+    pub fn reorder(&mut self, word_ids: &[String]) -> Result<()> {
+        if word_ids.len() != self.words.len() {
+            return Err(anyhow!("Every word of the set must be listed once"));
+        }
+        let mut words = Vec::with_capacity(self.words.len());
+        for id in word_ids {
+            let position = self
+                .words
+                .iter()
+                .position(|x| &x.id == id)
+                .ok_or_else(|| anyhow!("Word {id} not found in set"))?;
+            words.push(self.words.remove(position));
+        }
+        self.words = words;
+        Ok(())
+    }
+
+    /// Starts learning the set; the first review is due after the first
+    /// interval of the ladder.
+    pub fn start(&mut self, now: DateTime<Utc>) -> Result<()> {
+        if self.state != LearnSetState::Tobe {
+            return Err(anyhow!("Set is already started"));
+        }
+        if self.words.is_empty() {
+            return Err(anyhow!("An empty set cannot be started"));
+        }
+        self.state = LearnSetState::OneDay;
+        self.state_timestamp = Some(now);
+        self.started_at = Some(now);
         Ok(())
     }
 }
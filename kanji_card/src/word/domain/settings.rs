@@ -0,0 +1,123 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, FixedOffset, NaiveDate, Offset, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// One interval per learning stage, from `OneDay` to `TenDay`.
+pub const LADDER_LEN: usize = 6;
+
+const MAX_SET_SIZE: usize = 100;
+const MAX_UTC_OFFSET_MINUTES: i32 = 14 * 60;
+
+/// Pace of learning chosen by a user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct LearningSettings {
+    /// Words per set.
+    pub set_size: usize,
+    /// Days until the next review in each learning stage. The last entry is
+    /// the interval at which words leave their set.
+    pub interval_ladder: Vec<u32>,
+    /// Probability of recall the scheduler aims for when a card is due.
+    pub desired_retention: f64,
+    /// Sets that can be started per day; `None` means no limit.
+    pub max_new_sets_per_day: Option<usize>,
+    /// Graded answers per day; `None` means no limit.
+    pub max_reviews_per_day: Option<usize>,
+    /// Offset of the user's timezone; daily limits reset at local midnight.
+    pub utc_offset_minutes: i32,
+}
+
+impl Default for LearningSettings {
+    fn default() -> Self {
+        Self {
+            set_size: 8,
+            interval_ladder: vec![1, 2, 3, 5, 7, 10],
+            desired_retention: 0.9,
+            max_new_sets_per_day: None,
+            max_reviews_per_day: None,
+            utc_offset_minutes: 0,
+        }
+    }
+}
+
+impl LearningSettings {
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=MAX_SET_SIZE).contains(&self.set_size) {
+            return Err(anyhow!("Set size must be between 1 and {MAX_SET_SIZE}"));
+        }
+        if self.interval_ladder.len() != LADDER_LEN {
+            return Err(anyhow!("Interval ladder must have {LADDER_LEN} entries"));
+        }
+        if self.interval_ladder[0] == 0 || self.interval_ladder.windows(2).any(|x| x[0] >= x[1]) {
+            return Err(anyhow!(
+                "Interval ladder must be positive and strictly increasing"
+            ));
+        }
+        if !(0.7..=0.99).contains(&self.desired_retention) {
+            return Err(anyhow!("Desired retention must be between 0.7 and 0.99"));
+        }
+        if self.utc_offset_minutes.abs() > MAX_UTC_OFFSET_MINUTES {
+            return Err(anyhow!("UTC offset must be within 14 hours"));
+        }
+        Ok(())
+    }
+
+    /// Interval at which a word has been learned and leaves its set.
+    pub fn release_interval_days(&self) -> i64 {
+        self.interval_ladder.last().copied().unwrap_or_default() as i64
+    }
+
+    fn offset(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.utc_offset_minutes * 60).unwrap_or(Utc.fix())
+    }
+
+    /// The user's local date at `time`.
+    pub fn local_date(&self, time: DateTime<Utc>) -> NaiveDate {
+        time.with_timezone(&self.offset()).date_naive()
+    }
+
+    /// Start of the user's local `date`.
+    pub fn date_start(&self, date: NaiveDate) -> DateTime<Utc> {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        midnight
+            .and_local_timezone(self.offset())
+            .single()
+            .map(|x| x.with_timezone(&Utc))
+            .unwrap_or(midnight.and_utc())
+    }
+
+    /// Start of the user's local day containing `now`.
+    pub fn day_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.date_start(self.local_date(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn local_day_follows_the_utc_offset() {
+        let tokyo = LearningSettings {
+            utc_offset_minutes: 9 * 60,
+            ..Default::default()
+        };
+        let new_york = LearningSettings {
+            utc_offset_minutes: -5 * 60,
+            ..Default::default()
+        };
+        let now = time("2025-01-01T20:00:00Z");
+
+        assert_eq!(tokyo.local_date(now).to_string(), "2025-01-02");
+        assert_eq!(tokyo.day_start(now), time("2025-01-01T15:00:00Z"));
+        assert_eq!(new_york.local_date(now).to_string(), "2025-01-01");
+        assert_eq!(new_york.day_start(now), time("2025-01-01T05:00:00Z"));
+    }
+}
@@ -0,0 +1,230 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::UnicodeNormalization;
+use utoipa::ToSchema;
+
+use crate::{dictionary::kanji::is_kanji, word::domain::WordCard};
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DuplicateWord {
+    pub id: String,
+    pub word: String,
+    pub reading: Option<String>,
+    pub translation: String,
+    /// Set holding the word; `None` for released words.
+    pub set_id: Option<String>,
+}
+
+impl DuplicateWord {
+    pub fn new(card: &WordCard, set_id: Option<&str>) -> Self {
+        Self {
+            id: card.id().to_owned(),
+            word: card.word().to_owned(),
+            reading: card.known_reading().map(str::to_owned),
+            translation: card.translation().to_owned(),
+            set_id: set_id.map(str::to_owned),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MergeProposal {
+    pub keep: DuplicateWord,
+    pub duplicates: Vec<DuplicateWord>,
+}
+
+/// NFKC form without whitespace and with katakana folded to hiragana, so
+/// ｶﾀｶﾅ, カタカナ and かたかな compare equal.
+pub fn normalize(text: &str) -> String {
+    text.nfkc()
+        .filter(|x| !x.is_whitespace())
+        .map(|ch| match ch {
+            'ァ'..='ヶ' => char::from_u32(ch as u32 - 0x60).unwrap_or(ch),
+            _ => ch,
+        })
+        .collect()
+}
+
+/// Comparable form of a word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordKey {
+    text: String,
+    reading: String,
+    /// Kanji of the word in order; equal skeletons with equal readings
+    /// differ only in okurigana, e.g. 取り消し and 取消.
+    skeleton: String,
+}
+
+impl WordKey {
+    /// Uses the known reading, otherwise a kakasi transliteration.
+    pub fn new(word: &str, reading: Option<&str>) -> Self {
+        let text = normalize(word);
+        let reading = match reading.map(normalize).filter(|x| !x.is_empty()) {
+            Some(reading) => reading,
+            None => normalize(&kakasi::convert(&text).hiragana),
+        };
+        let skeleton = text.chars().filter(|x| is_kanji(*x)).collect();
+        Self {
+            text,
+            reading,
+            skeleton,
+        }
+    }
+
+    fn is_kana_only(&self) -> bool {
+        self.skeleton.is_empty()
+    }
+}
+
+/// Words a user already has, for skipping duplicates on save. Only matches
+/// that are certain count here; kana spellings of kanji words may be
+/// homophones and are left to [`group_duplicates`].
+#[derive(Debug, Default)]
+pub struct DuplicateIndex {
+    texts: HashSet<String>,
+    spellings: HashSet<(String, String)>,
+}
+
+impl DuplicateIndex {
+    pub fn insert(&mut self, word: &str, reading: Option<&str>) {
+        let key = WordKey::new(word, reading);
+        if !key.is_kana_only() {
+            self.spellings.insert((key.skeleton, key.reading));
+        }
+        self.texts.insert(key.text);
+    }
+
+    pub fn contains(&self, word: &str, reading: Option<&str>) -> bool {
+        let key = WordKey::new(word, reading);
+        self.texts.contains(&key.text)
+            || (!key.is_kana_only() && self.spellings.contains(&(key.skeleton, key.reading)))
+    }
+}
+
+/// Groups of indexes into `keys` that spell the same word: equal normalized
+/// text, equal kanji and reading, or a kana spelling equal to the reading
+/// of a kanji word, unless other kanji words share that reading. Groups are
+/// in order of their first member.
+pub fn group_duplicates(keys: &[WordKey]) -> Vec<Vec<usize>> {
+    let mut parents = (0..keys.len()).collect::<Vec<_>>();
+    fn root(parents: &mut [usize], index: usize) -> usize {
+        let mut index = index;
+        while parents[index] != index {
+            parents[index] = parents[parents[index]];
+            index = parents[index];
+        }
+        index
+    }
+
+    let mut texts: HashMap<&str, usize> = HashMap::new();
+    let mut spellings: HashMap<(&str, &str), usize> = HashMap::new();
+    // `None` when kanji words with different kanji share the reading.
+    let mut readings: HashMap<&str, Option<usize>> = HashMap::new();
+    let mut pairs = vec![];
+    for (index, key) in keys.iter().enumerate() {
+        if let Some(&other) = texts.get(key.text.as_str()) {
+            pairs.push((other, index));
+        }
+        texts.entry(&key.text).or_insert(index);
+        if !key.is_kana_only() {
+            let spelling = (key.skeleton.as_str(), key.reading.as_str());
+            if let Some(&other) = spellings.get(&spelling) {
+                pairs.push((other, index));
+            }
+            spellings.entry(spelling).or_insert(index);
+            let reading = readings.entry(&key.reading).or_insert(Some(index));
+            if reading.is_some_and(|x| keys[x].skeleton != key.skeleton) {
+                *reading = None;
+            }
+        }
+    }
+    for (index, key) in keys.iter().enumerate() {
+        if key.is_kana_only()
+            && let Some(&Some(other)) = readings.get(key.text.as_str())
+        {
+            pairs.push((other, index));
+        }
+    }
+
+    for (a, b) in pairs {
+        let (a, b) = (root(&mut parents, a), root(&mut parents, b));
+        parents[a.max(b)] = a.min(b);
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut positions: HashMap<usize, usize> = HashMap::new();
+    for index in 0..keys.len() {
+        let root = root(&mut parents, index);
+        match positions.get(&root) {
+            Some(&position) => groups[position].push(index),
+            None => {
+                positions.insert(root, groups.len());
+                groups.push(vec![index]);
+            }
+        }
+    }
+    groups.retain(|x| x.len() > 1);
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(words: &[(&str, Option<&str>)]) -> Vec<WordKey> {
+        words
+            .iter()
+            .map(|(word, reading)| WordKey::new(word, *reading))
+            .collect()
+    }
+
+    #[test]
+    fn normalize_folds_width_and_katakana() {
+        assert_eq!(normalize("ｶﾀｶﾅ"), "かたかな");
+        assert_eq!(normalize("カタカナ"), "かたかな");
+        assert_eq!(normalize(" 猫\u{3000}Ａ１ "), "猫A1");
+    }
+
+    #[test]
+    fn index_matches_width_and_kana_variants() {
+        let mut index = DuplicateIndex::default();
+        index.insert("コーヒー", None);
+        index.insert("Ｔシャツ", None);
+
+        assert!(index.contains("ｺｰﾋｰ", None));
+        assert!(index.contains("こーひー", None));
+        assert!(index.contains("Tしゃつ", None));
+        assert!(!index.contains("コーラ", None));
+    }
+
+    #[test]
+    fn index_matches_okurigana_variants_with_equal_reading() {
+        let mut index = DuplicateIndex::default();
+        index.insert("取り消し", Some("とりけし"));
+
+        assert!(index.contains("取消", Some("トリケシ")));
+        assert!(!index.contains("取消", Some("しゅしょう")));
+    }
+
+    #[test]
+    fn index_does_not_match_kana_spelling_of_kanji_word() {
+        let mut index = DuplicateIndex::default();
+        index.insert("橋", Some("はし"));
+
+        assert!(!index.contains("はし", None));
+    }
+
+    #[test]
+    fn groups_kana_spelling_with_its_only_kanji_word() {
+        let keys = keys(&[("猫", Some("ねこ")), ("ネコ", None), ("犬", Some("いぬ"))]);
+
+        assert_eq!(group_duplicates(&keys), vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn leaves_homophones_apart() {
+        let keys = keys(&[("橋", Some("はし")), ("箸", Some("はし")), ("はし", None)]);
+
+        assert!(group_duplicates(&keys).is_empty());
+    }
+}
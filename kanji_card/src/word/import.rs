@@ -0,0 +1,273 @@
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::Path};
+use utoipa::ToSchema;
+
+use crate::{anki, llm::ExtractedWord};
+
+/// Fields tried, in order, when the mapping does not name one. Numbers are
+/// 1-based column positions.
+const DEFAULT_WORD_FIELDS: [&str; 2] = ["Word", "1"];
+const DEFAULT_TRANSLATION_FIELDS: [&str; 2] = ["Translation", "2"];
+const DEFAULT_READING_FIELDS: [&str; 1] = ["Reading"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Apkg,
+    Csv,
+    Tsv,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "apkg" => Some(Self::Apkg),
+            "csv" => Some(Self::Csv),
+            "tsv" | "txt" => Some(Self::Tsv),
+            _ => None,
+        }
+    }
+}
+
+/// Which source fields become the word, translation and reading. Each
+/// value is a field or header name (case-insensitive) or a 1-based column
+/// number.
+#[derive(Debug, Clone)]
+pub struct FieldMapping {
+    pub word: Option<String>,
+    pub translation: Option<String>,
+    pub reading: Option<String>,
+    /// Whether the first row of a delimited file names the columns.
+    pub has_header: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, ToSchema)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Rows without a word or translation.
+    pub skipped: usize,
+    /// Rows repeating a word from the same file or one the user already has.
+    pub duplicates: usize,
+}
+
+type Record = Vec<(String, String)>;
+
+/// Parses a file and maps its rows to words. Returns the words, unique by
+/// word, together with a report of skipped and repeated rows.
+pub fn parse(
+    format: ImportFormat,
+    content: &[u8],
+    mapping: &FieldMapping,
+) -> Result<(Vec<ExtractedWord>, ImportReport)> {
+    let records = match format {
+        ImportFormat::Apkg => anki::read_package(content)?
+            .into_iter()
+            .map(|x| x.fields)
+            .collect(),
+        ImportFormat::Csv => read_delimited(content, b',', mapping.has_header)?,
+        ImportFormat::Tsv => read_delimited(content, b'\t', mapping.has_header)?,
+    };
+
+    let mut report = ImportReport::default();
+    let mut seen = HashSet::new();
+    let mut words = vec![];
+    for record in records {
+        let word = resolve(&record, mapping.word.as_deref(), &DEFAULT_WORD_FIELDS);
+        let translation = resolve(
+            &record,
+            mapping.translation.as_deref(),
+            &DEFAULT_TRANSLATION_FIELDS,
+        );
+        let (Some(word), Some(translation)) = (word, translation) else {
+            report.skipped += 1;
+            continue;
+        };
+
+        if !seen.insert(word.clone()) {
+            report.duplicates += 1;
+            continue;
+        }
+
+        words.push(ExtractedWord {
+            word,
+            translation,
+            reading: resolve(&record, mapping.reading.as_deref(), &DEFAULT_READING_FIELDS),
+        });
+    }
+
+    Ok((words, report))
+}
+
+fn read_delimited(content: &[u8], delimiter: u8, has_header: bool) -> Result<Vec<Record>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(has_header)
+        .flexible(true)
+        // Anki text exports start with `#separator:tab` style directives.
+        .comment(Some(b'#'))
+        .from_reader(content);
+
+    let headers = match has_header {
+        true => reader
+            .headers()?
+            .iter()
+            .map(|x| x.trim().to_owned())
+            .collect(),
+        false => vec![],
+    };
+
+    let mut records = vec![];
+    for row in reader.records() {
+        let row = row.map_err(|e| anyhow!("Invalid row: {}", e))?;
+        records.push(
+            row.iter()
+                .enumerate()
+                .map(|(position, value)| {
+                    let name = headers
+                        .get(position)
+                        .cloned()
+                        .unwrap_or_else(|| (position + 1).to_string());
+                    (name, value.trim().to_owned())
+                })
+                .collect(),
+        );
+    }
+    Ok(records)
+}
+
+fn resolve(record: &Record, field: Option<&str>, defaults: &[&str]) -> Option<String> {
+    match field {
+        Some(field) => lookup(record, field),
+        None => defaults.iter().find_map(|x| lookup(record, x)),
+    }
+}
+
+fn lookup(record: &Record, field: &str) -> Option<String> {
+    let value = record
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(field))
+        .or_else(|| {
+            field
+                .parse::<usize>()
+                .ok()
+                .and_then(|x| x.checked_sub(1))
+                .and_then(|x| record.get(x))
+        })
+        .map(|(_, value)| value.trim().to_owned())?;
+
+    (!value.is_empty()).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(word: Option<&str>, translation: Option<&str>, has_header: bool) -> FieldMapping {
+        FieldMapping {
+            word: word.map(str::to_owned),
+            translation: translation.map(str::to_owned),
+            reading: None,
+            has_header,
+        }
+    }
+
+    fn words(parsed: &(Vec<ExtractedWord>, ImportReport)) -> Vec<(&str, &str, Option<&str>)> {
+        parsed
+            .0
+            .iter()
+            .map(|x| {
+                (
+                    x.word.as_str(),
+                    x.translation.as_str(),
+                    x.reading.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn csv_maps_headers_by_default_names() {
+        let csv = "Translation,word,Reading\nкошка,猫,ねこ\nсобака,犬,\n";
+
+        let parsed = parse(
+            ImportFormat::Csv,
+            csv.as_bytes(),
+            &mapping(None, None, true),
+        )
+        .unwrap();
+
+        assert_eq!(
+            words(&parsed),
+            [("猫", "кошка", Some("ねこ")), ("犬", "собака", None)]
+        );
+    }
+
+    #[test]
+    fn csv_maps_explicit_columns_and_reports_bad_rows() {
+        let csv = "#separator:comma\nx,кошка,猫\nx,собака,\nx,кот,猫\n";
+
+        let parsed = parse(
+            ImportFormat::Csv,
+            csv.as_bytes(),
+            &mapping(Some("3"), Some("2"), false),
+        )
+        .unwrap();
+
+        assert_eq!(words(&parsed), [("猫", "кошка", None)]);
+        assert_eq!(parsed.1.skipped, 1);
+        assert_eq!(parsed.1.duplicates, 1);
+    }
+
+    #[test]
+    fn tsv_without_header_uses_the_first_two_columns() {
+        let tsv = "猫\tкошка\n犬\tсобака\textra\n";
+
+        let parsed = parse(
+            ImportFormat::Tsv,
+            tsv.as_bytes(),
+            &mapping(None, None, false),
+        )
+        .unwrap();
+
+        assert_eq!(
+            words(&parsed),
+            [("猫", "кошка", None), ("犬", "собака", None)]
+        );
+    }
+
+    #[test]
+    fn apkg_fields_map_by_name() {
+        let package = anki::write_package(&[anki::AnkiNote {
+            guid: "a".to_owned(),
+            word: "猫".to_owned(),
+            reading: Some("ねこ".to_owned()),
+            translation: "кошка".to_owned(),
+            state: None,
+        }])
+        .unwrap();
+
+        let parsed = parse(ImportFormat::Apkg, &package, &mapping(None, None, false)).unwrap();
+
+        assert_eq!(words(&parsed), [("猫", "кошка", Some("ねこ"))]);
+    }
+
+    #[test]
+    fn apkg_of_new_anki_versions_is_rejected() {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("collection.anki2", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.start_file(
+            "collection.anki21b",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+        let package = zip.finish().unwrap().into_inner();
+
+        let error = parse(ImportFormat::Apkg, &package, &mapping(None, None, false)).unwrap_err();
+
+        assert!(error.to_string().contains("Support older Anki versions"));
+    }
+}
@@ -0,0 +1,307 @@
+use axum::{
+    Extension,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    middleware,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use tracing::instrument;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    dictionary::{dictionary_service::DictionaryService, kanji::Kanji},
+    environment::auth::{Claims, JwtConfig, auth_middleware},
+    word::{
+        domain::WordCard, set_repository::LearnSetRepository,
+        word_release_repository::WordReleaseRepository,
+    },
+};
+
+#[derive(Clone)]
+struct KanjiState {
+    repository: Arc<dyn LearnSetRepository>,
+    release_repository: Arc<dyn WordReleaseRepository>,
+    dictionary: DictionaryService,
+}
+
+pub fn kanji_router(
+    set_repository: Arc<dyn LearnSetRepository>,
+    release_repository: Arc<dyn WordReleaseRepository>,
+    dictionary: DictionaryService,
+    jwt_config: JwtConfig,
+) -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(list_kanji))
+        .routes(routes!(get_coverage))
+        .routes(routes!(get_kanji))
+        .layer(middleware::from_fn_with_state(
+            jwt_config.clone(),
+            auth_middleware,
+        ))
+        .with_state(KanjiState {
+            repository: set_repository,
+            release_repository,
+            dictionary,
+        })
+}
+
+/// A user's card together with where it currently lives.
+struct OwnedCard {
+    card: WordCard,
+    set_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KanjiListQuery {
+    jlpt: Option<u8>,
+    grade: Option<u8>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct KanjiResponse {
+    literal: String,
+    meanings: Vec<String>,
+    on_readings: Vec<String>,
+    kun_readings: Vec<String>,
+    stroke_count: Option<u8>,
+    grade: Option<u8>,
+    jlpt: Option<u8>,
+    frequency: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct KanjiProgressResponse {
+    literal: String,
+    /// Empty when KANJIDIC2 is not imported or does not know the character.
+    kanji: Option<KanjiResponse>,
+    learning_words: usize,
+    released_words: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+struct KanjiWordResponse {
+    id: String,
+    word: String,
+    reading: String,
+    translation: String,
+    /// Set holding the word; empty for released words.
+    set_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct KanjiDetailsResponse {
+    literal: String,
+    kanji: Option<KanjiResponse>,
+    words: Vec<KanjiWordResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct JlptCoverage {
+    jlpt: u8,
+    total: usize,
+    covered: usize,
+    released: usize,
+}
+
+impl From<Kanji> for KanjiResponse {
+    fn from(kanji: Kanji) -> Self {
+        Self {
+            literal: kanji.literal().to_string(),
+            meanings: kanji.meanings().to_vec(),
+            on_readings: kanji.on_readings().to_vec(),
+            kun_readings: kanji.kun_readings().to_vec(),
+            stroke_count: kanji.stroke_count(),
+            grade: kanji.grade(),
+            jlpt: kanji.jlpt(),
+            frequency: kanji.frequency(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    params(
+        ("jlpt" = Option<u8>, Query, description = "Only kanji of this JLPT level"),
+        ("grade" = Option<u8>, Query, description = "Only kanji of this school grade")
+    ),
+    responses(
+        (status = 200, description = "Kanji covered by the user's words, most used first", body = Vec<KanjiProgressResponse>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn list_kanji(
+    State(state): State<KanjiState>,
+    Query(params): Query<KanjiListQuery>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<Vec<KanjiProgressResponse>>, (StatusCode, String)> {
+    let cards = load_cards(&state, &claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut progress: BTreeMap<char, (usize, usize)> = BTreeMap::new();
+    for owned in &cards {
+        for literal in owned.card.kanji() {
+            let counts = progress.entry(literal).or_default();
+            match owned.set_id {
+                Some(_) => counts.0 += 1,
+                None => counts.1 += 1,
+            }
+        }
+    }
+
+    let mut result = progress
+        .into_iter()
+        .map(|(literal, (learning_words, released_words))| {
+            (
+                literal,
+                state.dictionary.kanji(literal),
+                learning_words,
+                released_words,
+            )
+        })
+        .filter(|(_, kanji, _, _)| {
+            params
+                .jlpt
+                .is_none_or(|x| kanji.as_ref().and_then(|k| k.jlpt()) == Some(x))
+                && params
+                    .grade
+                    .is_none_or(|x| kanji.as_ref().and_then(|k| k.grade()) == Some(x))
+        })
+        .map(
+            |(literal, kanji, learning_words, released_words)| KanjiProgressResponse {
+                literal: literal.to_string(),
+                kanji: kanji.map(KanjiResponse::from),
+                learning_words,
+                released_words,
+            },
+        )
+        .collect::<Vec<_>>();
+    result.sort_by_key(|x| std::cmp::Reverse(x.learning_words + x.released_words));
+
+    Ok(axum::Json(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/coverage",
+    responses(
+        (status = 200, description = "Share of each JLPT level's kanji covered by the user's words", body = Vec<JlptCoverage>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn get_coverage(
+    State(state): State<KanjiState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<Vec<JlptCoverage>>, (StatusCode, String)> {
+    let cards = load_cards(&state, &claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // A kanji counts as released once any word containing it is released.
+    let mut covered: HashMap<char, bool> = HashMap::new();
+    for owned in &cards {
+        for literal in owned.card.kanji() {
+            *covered.entry(literal).or_default() |= owned.set_id.is_none();
+        }
+    }
+
+    let mut levels: BTreeMap<u8, JlptCoverage> = BTreeMap::new();
+    for kanji in state.dictionary.all_kanji() {
+        let Some(jlpt) = kanji.jlpt() else {
+            continue;
+        };
+
+        let level = levels.entry(jlpt).or_insert(JlptCoverage {
+            jlpt,
+            total: 0,
+            covered: 0,
+            released: 0,
+        });
+        level.total += 1;
+        if let Some(released) = covered.get(&kanji.literal()) {
+            level.covered += 1;
+            if *released {
+                level.released += 1;
+            }
+        }
+    }
+
+    Ok(axum::Json(levels.into_values().collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{literal}",
+    params(
+        ("literal" = String, Path, description = "Kanji character")
+    ),
+    responses(
+        (status = 200, description = "Kanji details with the user's words containing it", body = KanjiDetailsResponse),
+        (status = 404, description = "Kanji not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims), fields(literal = %literal))]
+async fn get_kanji(
+    State(state): State<KanjiState>,
+    Path(literal): Path<String>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<KanjiDetailsResponse>, (StatusCode, String)> {
+    let mut chars = literal.chars();
+    let (Some(literal), None) = (chars.next(), chars.next()) else {
+        return Err((StatusCode::NOT_FOUND, "Expected a single kanji".to_owned()));
+    };
+
+    let cards = load_cards(&state, &claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let words = cards
+        .into_iter()
+        .filter(|owned| owned.card.kanji().contains(&literal))
+        .map(|owned| KanjiWordResponse {
+            id: owned.card.id().to_owned(),
+            word: owned.card.word().to_owned(),
+            reading: owned.card.reading(),
+            translation: owned.card.translation().to_owned(),
+            set_id: owned.set_id,
+        })
+        .collect::<Vec<_>>();
+
+    let kanji = state.dictionary.kanji(literal);
+    if kanji.is_none() && words.is_empty() {
+        return Err((StatusCode::NOT_FOUND, "Kanji not found".to_owned()));
+    }
+
+    Ok(axum::Json(KanjiDetailsResponse {
+        literal: literal.to_string(),
+        kanji: kanji.map(KanjiResponse::from),
+        words,
+    }))
+}
+
+async fn load_cards(state: &KanjiState, user_login: &str) -> anyhow::Result<Vec<OwnedCard>> {
+    let mut cards = vec![];
+    for set in state.repository.list_all(user_login).await? {
+        for card in set.words() {
+            cards.push(OwnedCard {
+                card: card.clone(),
+                set_id: Some(set.id().to_owned()),
+            });
+        }
+    }
+
+    for card in state.release_repository.list_all_words(user_login).await? {
+        cards.push(OwnedCard { card, set_id: None });
+    }
+
+    Ok(cards)
+}
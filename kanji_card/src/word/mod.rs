@@ -1,6 +1,14 @@
 pub mod api;
+pub mod chunk;
 pub mod domain;
+pub mod duplicate;
+pub mod import;
+pub mod kanji;
 pub mod query;
+pub mod review_log_repository;
 pub mod set_repository;
 pub mod set_service;
+pub mod set_start_repository;
+pub mod settings_repository;
+pub mod stats;
 pub mod word_release_repository;
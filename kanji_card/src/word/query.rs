@@ -14,20 +14,27 @@ use utoipa_axum::{router::OpenApiRouter, routes};
 use crate::{
     environment::auth::{Claims, JwtConfig, auth_middleware},
     word::{
-        domain::set::LearnSetState, set_repository::LearnSetRepository,
+        domain::{schedule::Rating, set::LearnSetState},
+        review_log_repository::{ReviewLogFilter, ReviewLogRepository},
+        set_repository::LearnSetRepository,
+        settings_repository::LearningSettingsRepository,
         word_release_repository::WordReleaseRepository,
     },
 };
 
 #[derive(Clone)]
 struct QueryState {
-    repository: Arc<LearnSetRepository>,
-    release_repository: Arc<WordReleaseRepository>,
+    repository: Arc<dyn LearnSetRepository>,
+    release_repository: Arc<dyn WordReleaseRepository>,
+    review_log_repository: Arc<dyn ReviewLogRepository>,
+    settings_repository: Arc<dyn LearningSettingsRepository>,
 }
 
 pub fn query_router(
-    set_repository: LearnSetRepository,
-    release_repository: WordReleaseRepository,
+    set_repository: Arc<dyn LearnSetRepository>,
+    release_repository: Arc<dyn WordReleaseRepository>,
+    review_log_repository: Arc<dyn ReviewLogRepository>,
+    settings_repository: Arc<dyn LearningSettingsRepository>,
     jwt_config: JwtConfig,
 ) -> OpenApiRouter {
     OpenApiRouter::new()
@@ -36,14 +43,18 @@ pub fn query_router(
         .routes(routes!(list_current_sets))
         .routes(routes!(list_released_words))
         .routes(routes!(list_test_released_words))
+        .routes(routes!(list_due_released_words))
+        .routes(routes!(list_reviews))
         .routes(routes!(get_overview))
         .layer(middleware::from_fn_with_state(
             jwt_config.clone(),
             auth_middleware,
         ))
         .with_state(QueryState {
-            repository: Arc::new(set_repository),
-            release_repository: Arc::new(release_repository),
+            repository: set_repository,
+            release_repository,
+            review_log_repository,
+            settings_repository,
         })
 }
 
@@ -52,9 +63,20 @@ struct WordResponse {
     id: String,
     word: String,
     reading: Option<String>,
+    part_of_speech: Option<String>,
     translation: String,
 }
 
+#[derive(Serialize, ToSchema)]
+struct DueWordResponse {
+    id: String,
+    word: String,
+    reading: Option<String>,
+    part_of_speech: Option<String>,
+    translation: String,
+    due: Option<DateTime<Utc>>,
+}
+
 #[derive(Serialize, ToSchema)]
 struct CurrentSets {
     word_count_to_learn: usize,
@@ -67,6 +89,7 @@ struct CurrentSets {
 #[derive(Serialize, ToSchema)]
 struct SetResponse {
     id: String,
+    name: Option<String>,
     state: LearnSetState,
     words: Vec<WordResponse>,
     time_to_learn: Option<DateTime<Utc>>,
@@ -91,6 +114,26 @@ struct ReleasedSetsQuery {
     search: Option<String>,
 }
 
+#[derive(Serialize, ToSchema)]
+struct ReviewLogResponse {
+    id: String,
+    card_id: String,
+    word: String,
+    set_id: Option<String>,
+    timestamp: DateTime<Utc>,
+    rating: Rating,
+    response_time_ms: Option<u64>,
+    state_before: Option<LearnSetState>,
+    state_after: Option<LearnSetState>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReviewLogQuery {
+    card_id: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
 #[utoipa::path(
     get,
     path = "/sets/{id}",
@@ -109,10 +152,16 @@ async fn get_set(
     Path(set_id): Path<String>,
     Extension(claims): Extension<Claims>,
 ) -> Result<axum::Json<SetResponse>, (StatusCode, String)> {
+    let settings = state
+        .settings_repository
+        .load(&claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     match state.repository.load(&claims.sub, &set_id).await {
         Ok(card_set) => {
             let response = SetResponse {
                 id: card_set.id().to_string(),
+                name: card_set.name().map(str::to_owned),
                 state: card_set.state().clone(),
                 words: card_set
                     .words()
@@ -121,11 +170,12 @@ async fn get_set(
                         id: w.id().to_string(),
                         word: w.word().to_string(),
                         reading: Some(w.reading()),
+                        part_of_speech: w.part_of_speech().map(str::to_owned),
                         translation: w.translation().to_string(),
                     })
                     .collect(),
-                time_to_learn: card_set.time_to_learn(),
-                need_to_learn: card_set.need_to_learn(),
+                time_to_learn: card_set.time_to_learn(&settings),
+                need_to_learn: card_set.need_to_learn(&settings),
             };
             Ok(axum::Json(response))
         }
@@ -146,6 +196,11 @@ async fn list_tobe_sets(
     State(state): State<QueryState>,
     Extension(claims): Extension<Claims>,
 ) -> Result<axum::Json<Vec<SetResponse>>, (StatusCode, String)> {
+    let settings = state
+        .settings_repository
+        .load(&claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     match state.repository.list_all(&claims.sub).await {
         Ok(sets) => {
             let response = sets
@@ -153,6 +208,7 @@ async fn list_tobe_sets(
                 .filter(|set| set.state() == &LearnSetState::Tobe)
                 .map(|set| SetResponse {
                     id: set.id().to_string(),
+                    name: set.name().map(str::to_owned),
                     state: set.state().clone(),
                     words: set
                         .words()
@@ -161,11 +217,12 @@ async fn list_tobe_sets(
                             id: w.id().to_string(),
                             word: w.word().to_string(),
                             reading: Some(w.reading()),
+                            part_of_speech: w.part_of_speech().map(str::to_owned),
                             translation: w.translation().to_string(),
                         })
                         .collect(),
-                    time_to_learn: set.time_to_learn(),
-                    need_to_learn: set.need_to_learn(),
+                    time_to_learn: set.time_to_learn(&settings),
+                    need_to_learn: set.need_to_learn(&settings),
                 })
                 .collect();
             Ok(axum::Json(response))
@@ -187,6 +244,11 @@ async fn list_current_sets(
     State(state): State<QueryState>,
     Extension(claims): Extension<Claims>,
 ) -> Result<axum::Json<CurrentSets>, (StatusCode, String)> {
+    let settings = state
+        .settings_repository
+        .load(&claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     match state.repository.list_all(&claims.sub).await {
         Ok(sets) => {
             let mut need_to_learn = Vec::new();
@@ -198,6 +260,7 @@ async fn list_current_sets(
                 if set.state() != &LearnSetState::Tobe {
                     let set_response = SetResponse {
                         id: set.id().to_string(),
+                        name: set.name().map(str::to_owned),
                         state: set.state().clone(),
                         words: set
                             .words()
@@ -206,14 +269,15 @@ async fn list_current_sets(
                                 id: w.id().to_string(),
                                 word: w.word().to_string(),
                                 reading: Some(w.reading()),
+                                part_of_speech: w.part_of_speech().map(str::to_owned),
                                 translation: w.translation().to_string(),
                             })
                             .collect(),
-                        time_to_learn: set.time_to_learn(),
-                        need_to_learn: set.need_to_learn(),
+                        time_to_learn: set.time_to_learn(&settings),
+                        need_to_learn: set.need_to_learn(&settings),
                     };
 
-                    if set.need_to_learn() {
+                    if set.need_to_learn(&settings) {
                         word_count_to_learn += set.words().len();
                         need_to_learn.push(set_response);
                     } else {
@@ -269,6 +333,7 @@ async fn list_released_words(
                     id: w.id().to_string(),
                     word: w.word().to_string(),
                     reading: Some(w.reading()),
+                    part_of_speech: w.part_of_speech().map(str::to_owned),
                     translation: w.translation().to_string(),
                 })
                 .collect();
@@ -327,6 +392,7 @@ async fn get_overview(
                                 id: w.id().to_string(),
                                 word: w.word().to_string(),
                                 reading: Some(w.reading()),
+                                part_of_speech: w.part_of_speech().map(str::to_owned),
                                 translation: w.translation().to_string(),
                             }),
                     );
@@ -349,6 +415,7 @@ async fn get_overview(
                             id: w.id().to_string(),
                             word: w.word().to_string(),
                             reading: Some(w.reading()),
+                            part_of_speech: w.part_of_speech().map(str::to_owned),
                             translation: w.translation().to_string(),
                         }),
                 );
@@ -390,7 +457,121 @@ async fn list_test_released_words(
                     id: w.id().to_string(),
                     word: w.word().to_string(),
                     reading: Some(w.reading()),
+                    part_of_speech: w.part_of_speech().map(str::to_owned),
+                    translation: w.translation().to_string(),
+                })
+                .collect();
+
+            Ok(axum::Json(result))
+        }
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/sets/released/due",
+    responses(
+        (status = 200, description = "Released cards due for a maintenance review, oldest due first, at most as many as reviews left for today", body = Vec<DueWordResponse>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn list_due_released_words(
+    State(state): State<QueryState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<Vec<DueWordResponse>>, (StatusCode, String)> {
+    let now = Utc::now();
+    let settings = state
+        .settings_repository
+        .load(&claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let remaining_reviews = match settings.max_reviews_per_day {
+        Some(limit) => {
+            let filter = ReviewLogFilter {
+                from: Some(settings.day_start(now)),
+                ..Default::default()
+            };
+            let done = state
+                .review_log_repository
+                .list(&claims.sub, &filter)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+                .len();
+            Some(limit.saturating_sub(done))
+        }
+        None => None,
+    };
+
+    match state.release_repository.list_all_words(&claims.sub).await {
+        Ok(cards) => {
+            let mut cards = cards
+                .into_iter()
+                .filter(|w| w.due().is_some_and(|due| due <= now))
+                .collect::<Vec<_>>();
+            cards.sort_by_key(|w| w.due());
+            if let Some(remaining_reviews) = remaining_reviews {
+                cards.truncate(remaining_reviews);
+            }
+
+            let result = cards
+                .iter()
+                .map(|w| DueWordResponse {
+                    id: w.id().to_string(),
+                    word: w.word().to_string(),
+                    reading: Some(w.reading()),
+                    part_of_speech: w.part_of_speech().map(str::to_owned),
                     translation: w.translation().to_string(),
+                    due: w.due(),
+                })
+                .collect();
+
+            Ok(axum::Json(result))
+        }
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/reviews",
+    params(
+        ("card_id" = Option<String>, Query, description = "Only reviews of this card"),
+        ("from" = Option<DateTime<Utc>>, Query, description = "Reviews at or after this time"),
+        ("to" = Option<DateTime<Utc>>, Query, description = "Reviews before this time")
+    ),
+    responses(
+        (status = 200, description = "Review history retrieved successfully in chronological order", body = Vec<ReviewLogResponse>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims), fields(card_id = ?params.card_id))]
+async fn list_reviews(
+    State(state): State<QueryState>,
+    Query(params): Query<ReviewLogQuery>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<Vec<ReviewLogResponse>>, (StatusCode, String)> {
+    let filter = ReviewLogFilter {
+        card_id: params.card_id,
+        from: params.from,
+        to: params.to,
+    };
+
+    match state.review_log_repository.list(&claims.sub, &filter).await {
+        Ok(entries) => {
+            let result = entries
+                .iter()
+                .map(|entry| ReviewLogResponse {
+                    id: entry.id().to_string(),
+                    card_id: entry.card_id().to_string(),
+                    word: entry.word().to_string(),
+                    set_id: entry.set_id().map(|x| x.to_string()),
+                    timestamp: entry.timestamp(),
+                    rating: entry.rating(),
+                    response_time_ms: entry.response_time_ms(),
+                    state_before: entry.state_before().cloned(),
+                    state_after: entry.state_after().cloned(),
                 })
                 .collect();
 
@@ -0,0 +1,217 @@
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::params;
+use std::path::{Path, PathBuf};
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::{storage::SqliteStorage, word::domain::review::ReviewLogEntry};
+
+const STORAGE_DIR: &str = "review_log";
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ReviewLogFilter {
+    pub card_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl ReviewLogFilter {
+    fn matches(&self, entry: &ReviewLogEntry) -> bool {
+        self.card_id.as_deref().is_none_or(|x| x == entry.card_id())
+            && self.from.is_none_or(|x| entry.timestamp() >= x)
+            && self.to.is_none_or(|x| entry.timestamp() < x)
+    }
+}
+
+/// Append-only history of review answers, ordered by time.
+#[async_trait]
+pub trait ReviewLogRepository: Send + Sync {
+    async fn append(&self, user_login: &str, entries: &[ReviewLogEntry]) -> anyhow::Result<()>;
+
+    async fn list(
+        &self,
+        user_login: &str,
+        filter: &ReviewLogFilter,
+    ) -> anyhow::Result<Vec<ReviewLogEntry>>;
+}
+
+#[derive(Clone)]
+pub struct FileReviewLogRepository {
+    storage_dir: PathBuf,
+}
+
+impl FileReviewLogRepository {
+    pub async fn new(data_dir: &Path) -> anyhow::Result<Self> {
+        let storage_dir = data_dir.join(STORAGE_DIR);
+        fs::create_dir_all(&storage_dir).await?;
+
+        Ok(Self { storage_dir })
+    }
+
+    fn get_user_path(&self, user_login: &str) -> PathBuf {
+        self.storage_dir.join(format!("{user_login}.jsonl"))
+    }
+}
+
+#[async_trait]
+impl ReviewLogRepository for FileReviewLogRepository {
+    async fn append(&self, user_login: &str, entries: &[ReviewLogEntry]) -> anyhow::Result<()> {
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.get_user_path(user_login))
+            .await?;
+        file.write_all(lines.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        user_login: &str,
+        filter: &ReviewLogFilter,
+    ) -> anyhow::Result<Vec<ReviewLogEntry>> {
+        let file_path = self.get_user_path(user_login);
+        if !file_path.exists() {
+            return Ok(vec![]);
+        }
+
+        let content = fs::read_to_string(file_path).await?;
+        let entries = content
+            .lines()
+            .map(serde_json::from_str::<ReviewLogEntry>)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries
+            .into_iter()
+            .filter(|entry| filter.matches(entry))
+            .collect())
+    }
+}
+
+#[derive(Clone)]
+pub struct SqliteReviewLogRepository {
+    storage: SqliteStorage,
+}
+
+impl SqliteReviewLogRepository {
+    pub async fn new(storage: SqliteStorage) -> anyhow::Result<Self> {
+        storage
+            .call(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS review_log (
+                        user_login TEXT NOT NULL,
+                        id TEXT NOT NULL,
+                        card_id TEXT NOT NULL,
+                        timestamp TEXT NOT NULL,
+                        data TEXT NOT NULL,
+                        PRIMARY KEY (user_login, id)
+                    );
+                    CREATE INDEX IF NOT EXISTS review_log_user_timestamp
+                        ON review_log (user_login, timestamp);",
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(Self { storage })
+    }
+}
+
+#[async_trait]
+impl ReviewLogRepository for SqliteReviewLogRepository {
+    async fn append(&self, user_login: &str, entries: &[ReviewLogEntry]) -> anyhow::Result<()> {
+        let user_login = user_login.to_owned();
+        let rows = entries
+            .iter()
+            .map(|entry| {
+                Ok((
+                    entry.id().to_owned(),
+                    entry.card_id().to_owned(),
+                    format_timestamp(entry.timestamp()),
+                    serde_json::to_string(entry)?,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        self.storage
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                {
+                    let mut statement = tx.prepare(
+                        "INSERT INTO review_log (user_login, id, card_id, timestamp, data)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                    )?;
+                    for (id, card_id, timestamp, json) in rows {
+                        statement.execute(params![user_login, id, card_id, timestamp, json])?;
+                    }
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn list(
+        &self,
+        user_login: &str,
+        filter: &ReviewLogFilter,
+    ) -> anyhow::Result<Vec<ReviewLogEntry>> {
+        let user_login = user_login.to_owned();
+        let card_id = filter.card_id.clone();
+        let from = filter.from.map(format_timestamp);
+        let to = filter.to.map(format_timestamp);
+        let rows = self
+            .storage
+            .call(move |conn| {
+                let mut statement = conn.prepare(
+                    "SELECT data FROM review_log
+                     WHERE user_login = ?1
+                       AND (?2 IS NULL OR card_id = ?2)
+                       AND (?3 IS NULL OR timestamp >= ?3)
+                       AND (?4 IS NULL OR timestamp < ?4)
+                     ORDER BY timestamp, id",
+                )?;
+                let rows = statement
+                    .query_map(params![user_login, card_id, from, to], |row| {
+                        row.get::<_, String>(0)
+                    })?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn corrupt_entry_fails_the_listing() {
+        let dir = TempDir::new().unwrap();
+        let repository = FileReviewLogRepository::new(dir.path()).await.unwrap();
+        fs::write(repository.get_user_path("user"), "{\"id\":\n")
+            .await
+            .unwrap();
+
+        let result = repository.list("user", &ReviewLogFilter::default()).await;
+
+        assert!(result.is_err());
+    }
+}
@@ -1,19 +1,34 @@
 use anyhow::anyhow;
-use std::path::PathBuf;
+use async_trait::async_trait;
+use rusqlite::{OptionalExtension, params};
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
-use crate::word::domain::set::LearnSet;
+use crate::{storage::SqliteStorage, word::domain::set::LearnSet};
 
-const STORAGE_DIR: &str = "data/cardsets";
+const STORAGE_DIR: &str = "cardsets";
+
+#[async_trait]
+pub trait LearnSetRepository: Send + Sync {
+    async fn remove(&self, user_login: &str, card_set_id: &str) -> anyhow::Result<()>;
+
+    async fn save(&self, user_login: &str, card_set: &LearnSet) -> anyhow::Result<()>;
+
+    async fn load(&self, user_login: &str, id: &str) -> anyhow::Result<LearnSet>;
+
+    async fn list_ids(&self, user_login: &str) -> anyhow::Result<Vec<String>>;
+
+    async fn list_all(&self, user_login: &str) -> anyhow::Result<Vec<LearnSet>>;
+}
 
 #[derive(Clone)]
-pub struct LearnSetRepository {
+pub struct FileLearnSetRepository {
     storage_dir: PathBuf,
 }
 
-impl LearnSetRepository {
-    pub async fn new() -> anyhow::Result<Self> {
-        let storage_dir = PathBuf::from(STORAGE_DIR);
+impl FileLearnSetRepository {
+    pub async fn new(data_dir: &Path) -> anyhow::Result<Self> {
+        let storage_dir = data_dir.join(STORAGE_DIR);
         fs::create_dir_all(&storage_dir).await?;
 
         let repository = Self { storage_dir };
@@ -23,8 +38,11 @@ impl LearnSetRepository {
     fn get_user_path(&self, user_login: &str) -> PathBuf {
         self.storage_dir.join(user_login)
     }
+}
 
-    pub async fn remove(&self, user_login: &str, card_set_id: &str) -> anyhow::Result<()> {
+#[async_trait]
+impl LearnSetRepository for FileLearnSetRepository {
+    async fn remove(&self, user_login: &str, card_set_id: &str) -> anyhow::Result<()> {
         let file_path = self
             .get_user_path(user_login)
             .join(format!("{card_set_id}.json"));
@@ -33,17 +51,17 @@ impl LearnSetRepository {
         Ok(())
     }
 
-    pub async fn save(&self, user_login: &str, card_set: &LearnSet) -> anyhow::Result<()> {
+    async fn save(&self, user_login: &str, card_set: &LearnSet) -> anyhow::Result<()> {
         let json = serde_json::to_string_pretty(card_set)?;
-        let file_path = self
-            .get_user_path(user_login)
-            .join(format!("{}.json", card_set.id()));
+        let user_dir = self.get_user_path(user_login);
+        fs::create_dir_all(&user_dir).await?;
+        let file_path = user_dir.join(format!("{}.json", card_set.id()));
 
         fs::write(file_path, json).await?;
         Ok(())
     }
 
-    pub async fn load(&self, user_login: &str, id: &str) -> anyhow::Result<LearnSet> {
+    async fn load(&self, user_login: &str, id: &str) -> anyhow::Result<LearnSet> {
         let file_path = self.get_user_path(user_login).join(format!("{id}.json"));
         if file_path.exists() {
             let json = fs::read_to_string(file_path).await?;
@@ -52,7 +70,7 @@ impl LearnSetRepository {
         Err(anyhow!("Card set not found"))
     }
 
-    pub async fn list_ids(&self, user_login: &str) -> anyhow::Result<Vec<String>> {
+    async fn list_ids(&self, user_login: &str) -> anyhow::Result<Vec<String>> {
         let mut ids = Vec::new();
         let state_dir = self.get_user_path(user_login);
 
@@ -70,15 +88,130 @@ impl LearnSetRepository {
         Ok(ids)
     }
 
-    pub async fn list_all(&self, user_login: &str) -> anyhow::Result<Vec<LearnSet>> {
+    async fn list_all(&self, user_login: &str) -> anyhow::Result<Vec<LearnSet>> {
         let mut all_sets = Vec::new();
 
         for id in self.list_ids(user_login).await? {
-            if let Ok(set) = self.load(user_login, &id).await {
-                all_sets.push(set);
-            }
+            all_sets.push(self.load(user_login, &id).await?);
         }
 
         Ok(all_sets)
     }
 }
+
+#[derive(Clone)]
+pub struct SqliteLearnSetRepository {
+    storage: SqliteStorage,
+}
+
+impl SqliteLearnSetRepository {
+    pub async fn new(storage: SqliteStorage) -> anyhow::Result<Self> {
+        storage
+            .call(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS learn_sets (
+                        user_login TEXT NOT NULL,
+                        id TEXT NOT NULL,
+                        data TEXT NOT NULL,
+                        PRIMARY KEY (user_login, id)
+                    );",
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(Self { storage })
+    }
+}
+
+#[async_trait]
+impl LearnSetRepository for SqliteLearnSetRepository {
+    async fn remove(&self, user_login: &str, card_set_id: &str) -> anyhow::Result<()> {
+        let user_login = user_login.to_owned();
+        let card_set_id = card_set_id.to_owned();
+        self.storage
+            .call(move |conn| {
+                let removed = conn.execute(
+                    "DELETE FROM learn_sets WHERE user_login = ?1 AND id = ?2",
+                    params![user_login, card_set_id],
+                )?;
+                if removed == 0 {
+                    return Err(anyhow!("Card set not found"));
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn save(&self, user_login: &str, card_set: &LearnSet) -> anyhow::Result<()> {
+        let json = serde_json::to_string(card_set)?;
+        let user_login = user_login.to_owned();
+        let id = card_set.id().to_owned();
+        self.storage
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO learn_sets (user_login, id, data) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (user_login, id) DO UPDATE SET data = excluded.data",
+                    params![user_login, id, json],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn load(&self, user_login: &str, id: &str) -> anyhow::Result<LearnSet> {
+        let user_login = user_login.to_owned();
+        let id = id.to_owned();
+        let json = self
+            .storage
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT data FROM learn_sets WHERE user_login = ?1 AND id = ?2",
+                        params![user_login, id],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .optional()?)
+            })
+            .await?;
+
+        match json {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Err(anyhow!("Card set not found")),
+        }
+    }
+
+    async fn list_ids(&self, user_login: &str) -> anyhow::Result<Vec<String>> {
+        let user_login = user_login.to_owned();
+        self.storage
+            .call(move |conn| {
+                let mut statement =
+                    conn.prepare("SELECT id FROM learn_sets WHERE user_login = ?1")?;
+                let ids = statement
+                    .query_map(params![user_login], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(ids)
+            })
+            .await
+    }
+
+    async fn list_all(&self, user_login: &str) -> anyhow::Result<Vec<LearnSet>> {
+        let user_login = user_login.to_owned();
+        let rows = self
+            .storage
+            .call(move |conn| {
+                let mut statement =
+                    conn.prepare("SELECT data FROM learn_sets WHERE user_login = ?1")?;
+                let rows = statement
+                    .query_map(params![user_login], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<_, _>>()?)
+    }
+}
@@ -1,66 +1,419 @@
 use crate::{
+    anki::{self, AnkiNote},
     config::Settings,
+    dictionary::dictionary_service::DictionaryService,
     llm::{ExtractedWord, LlmService, WordsResponse},
+    usage::{record::UsageEndpoint, usage_service::QuotaExceeded},
     word::{
-        domain::set::{LearnSet, LearnSetState},
+        chunk,
+        domain::{
+            WordCard, WordEdit,
+            review::{ReviewGrade, ReviewLogEntry},
+            schedule::{Rating, Scheduler},
+            set::{LearnSet, LearnSetState},
+            settings::LearningSettings,
+        },
+        duplicate::{self, DuplicateIndex, DuplicateWord, MergeProposal, WordKey},
+        import::{self, FieldMapping, ImportFormat, ImportReport},
+        review_log_repository::{ReviewLogFilter, ReviewLogRepository},
         set_repository::LearnSetRepository,
+        set_start_repository::SetStartRepository,
+        settings_repository::LearningSettingsRepository,
         word_release_repository::WordReleaseRepository,
     },
 };
 use anyhow::Result;
-use std::collections::HashSet;
-use tracing::{info, instrument};
+use chrono::Utc;
+use futures_util::{StreamExt, stream};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+use tracing::{info, instrument, warn};
+use ulid::Ulid;
+
+/// Receives extracted words as soon as a part of the input is processed.
+pub type WordsProgress<'a> = dyn Fn(&[ExtractedWord]) + Send + Sync + 'a;
+
+/// Words found in a text, without the ones the user already has.
+#[derive(Debug, Clone, Default)]
+pub struct TextExtraction {
+    pub words: Vec<ExtractedWord>,
+    /// Words of the text skipped because they are in a set or released.
+    pub known_words: Vec<String>,
+}
+
+/// Rejected change of a word or a set; other errors are storage failures.
+#[derive(Debug)]
+pub enum WordError {
+    NotFound(String),
+    Invalid(String),
+    /// A daily limit of the user's learning settings is used up.
+    LimitReached(String),
+}
+
+impl fmt::Display for WordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordError::NotFound(message)
+            | WordError::Invalid(message)
+            | WordError::LimitReached(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for WordError {}
+
+/// State written to exported notes of released words.
+const RELEASED_STATE: &str = "Released";
 
 pub struct SetService {
-    set_repository: LearnSetRepository,
-    release_repository: WordReleaseRepository,
+    set_repository: Arc<dyn LearnSetRepository>,
+    release_repository: Arc<dyn WordReleaseRepository>,
+    review_log_repository: Arc<dyn ReviewLogRepository>,
+    set_start_repository: Arc<dyn SetStartRepository>,
+    settings_repository: Arc<dyn LearningSettingsRepository>,
     llm_service: LlmService,
+    dictionary: DictionaryService,
+    scheduler: Scheduler,
     config: Settings,
 }
 
 impl SetService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        set_repository: LearnSetRepository,
-        release_repository: WordReleaseRepository,
+        set_repository: Arc<dyn LearnSetRepository>,
+        release_repository: Arc<dyn WordReleaseRepository>,
+        review_log_repository: Arc<dyn ReviewLogRepository>,
+        set_start_repository: Arc<dyn SetStartRepository>,
+        settings_repository: Arc<dyn LearningSettingsRepository>,
         llm_service: LlmService,
+        dictionary: DictionaryService,
         config: Settings,
     ) -> Self {
         Self {
             set_repository,
             release_repository,
+            review_log_repository,
+            set_start_repository,
+            settings_repository,
             llm_service,
+            dictionary,
+            scheduler: Scheduler::default(),
             config,
         }
     }
 
-    #[instrument(skip(self, text))]
-    pub async fn extract_words_from_text(&self, text: String) -> Result<Vec<ExtractedWord>> {
-        info!("Extracting words from text");
+    fn llm(&self, user_login: &str, endpoint: UsageEndpoint, bypass_cache: bool) -> LlmService {
+        let llm_service = self.llm_service.for_user(user_login, endpoint);
+        if bypass_cache {
+            llm_service.without_cache()
+        } else {
+            llm_service
+        }
+    }
+
+    fn scheduler(&self, settings: &LearningSettings) -> Scheduler {
+        self.scheduler
+            .clone()
+            .with_desired_retention(settings.desired_retention)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn learning_settings(&self, user_login: &str) -> Result<LearningSettings> {
+        self.settings_repository.load(user_login).await
+    }
+
+    #[instrument(skip(self, settings))]
+    pub async fn update_learning_settings(
+        &self,
+        user_login: &str,
+        settings: LearningSettings,
+    ) -> Result<LearningSettings> {
+        settings
+            .validate()
+            .map_err(|e| WordError::Invalid(e.to_string()))?;
+        self.settings_repository.save(user_login, &settings).await?;
+        info!("Updated learning settings of user {}", user_login);
+        Ok(settings)
+    }
+
+    /// Fails when starting `new_sets` sets or grading `reviews` answers
+    /// would exceed the user's limits for the current local day.
+    async fn check_daily_limits(
+        &self,
+        user_login: &str,
+        settings: &LearningSettings,
+        new_sets: usize,
+        reviews: usize,
+    ) -> Result<()> {
+        let day_start = settings.day_start(Utc::now());
+        if let Some(limit) = settings.max_new_sets_per_day
+            && new_sets > 0
+        {
+            let started = self
+                .set_start_repository
+                .count_since(user_login, day_start)
+                .await?;
+            if started + new_sets > limit {
+                return Err(WordError::LimitReached(format!(
+                    "Daily limit of {limit} new sets reached"
+                ))
+                .into());
+            }
+        }
+
+        if let Some(limit) = settings.max_reviews_per_day
+            && reviews > 0
+        {
+            let filter = ReviewLogFilter {
+                from: Some(day_start),
+                ..Default::default()
+            };
+            let done = self
+                .review_log_repository
+                .list(user_login, &filter)
+                .await?
+                .len();
+            if done + reviews > limit {
+                return Err(WordError::LimitReached(format!(
+                    "Daily limit of {limit} reviews reached, {done} done today"
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Extracts words chunk by chunk so long texts fit the model context;
+    /// `progress` receives the new words of every finished chunk. With
+    /// `skip_known` the words the user already has are reported as
+    /// `known_words` instead; they are cut out of the prompt when morphology
+    /// is loaded and filtered out of the answer otherwise.
+    #[instrument(skip(self, text, progress))]
+    pub async fn extract_words_from_text(
+        &self,
+        user_login: &str,
+        text: String,
+        bypass_cache: bool,
+        skip_known: bool,
+        progress: Option<&WordsProgress<'_>>,
+    ) -> Result<TextExtraction> {
+        let known = match skip_known {
+            true => Some(self.known_words(user_login).await?),
+            false => None,
+        };
+        if skip_known && !self.dictionary.can_lemmatize() {
+            warn!(
+                "Morphology is not loaded, known words are filtered out of the answer instead of the prompt"
+            );
+        }
+        let chunks = chunk::chunk_text(&text, self.config.extraction.chunk_chars);
+        let chunk_count = chunks.len();
+        info!("Extracting words from text in {} chunks", chunk_count);
+
+        let known = known.as_ref();
+        let mut results = stream::iter(chunks.into_iter().enumerate())
+            .map(|(index, chunk)| async move {
+                (
+                    index,
+                    self.extract_words_from_chunk(user_login, &chunk, bypass_cache, known)
+                        .await,
+                )
+            })
+            .buffer_unordered(self.config.extraction.concurrency.max(1));
+
+        let mut extracted = vec![Vec::new(); chunk_count];
+        let mut skipped = vec![Vec::new(); chunk_count];
+        let mut seen = HashSet::new();
+        while let Some((index, result)) = results.next().await {
+            let (words, known_words) = result?;
+            if let Some(progress) = progress {
+                let new_words = words
+                    .iter()
+                    .filter(|x| seen.insert(x.word.clone()))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if !new_words.is_empty() {
+                    progress(&new_words);
+                }
+            }
+            extracted[index] = words;
+            skipped[index] = known_words;
+        }
+
+        let words = chunk::merge_words(extracted);
+        let mut seen = HashSet::new();
+        let known_words = skipped
+            .into_iter()
+            .flatten()
+            .filter(|x| seen.insert(x.clone()))
+            .collect::<Vec<_>>();
+        info!(
+            "Successfully extracted {} words from text, skipped {} known",
+            words.len(),
+            known_words.len()
+        );
+        Ok(TextExtraction { words, known_words })
+    }
+
+    /// Words of all sets and released words of the user, together with the
+    /// dictionary forms of words saved before lemmatization.
+    async fn known_words(&self, user_login: &str) -> Result<DuplicateIndex> {
+        let mut words = self.release_repository.list_all_words(user_login).await?;
+        for set in self.set_repository.list_all(user_login).await? {
+            words.extend(set.words().iter().cloned());
+        }
+
+        let mut known_words = DuplicateIndex::default();
+        for card in words {
+            if let Some(lemma) = self.dictionary.lemmatize(card.word()) {
+                known_words.insert(&lemma.base_form, lemma.reading.as_deref());
+            }
+            known_words.insert(card.word(), card.known_reading());
+        }
+        Ok(known_words)
+    }
+
+    /// Groups of words in sets and released words that look like the same
+    /// word. Each group proposes to keep a released word, or else the oldest.
+    #[instrument(skip(self))]
+    pub async fn find_duplicates(&self, user_login: &str) -> Result<Vec<MergeProposal>> {
+        let mut words = self
+            .release_repository
+            .list_all_words(user_login)
+            .await?
+            .iter()
+            .map(|x| DuplicateWord::new(x, None))
+            .collect::<Vec<_>>();
+        for set in self.set_repository.list_all(user_login).await? {
+            for card in set.words() {
+                words.push(DuplicateWord::new(card, Some(set.id())));
+            }
+        }
+
+        let keys = words
+            .iter()
+            .map(|x| WordKey::new(&x.word, x.reading.as_deref()))
+            .collect::<Vec<_>>();
+        let proposals = duplicate::group_duplicates(&keys)
+            .into_iter()
+            .map(|group| {
+                let mut group = group
+                    .into_iter()
+                    .map(|x| words[x].clone())
+                    .collect::<Vec<_>>();
+                group.sort_by(|a, b| (a.set_id.is_some(), &a.id).cmp(&(b.set_id.is_some(), &b.id)));
+                let keep = group.remove(0);
+                MergeProposal {
+                    keep,
+                    duplicates: group,
+                }
+            })
+            .collect::<Vec<_>>();
+        info!(
+            "Found {} groups of duplicates among {} words",
+            proposals.len(),
+            words.len()
+        );
+        Ok(proposals)
+    }
+
+    /// Returns the extracted words and the known words skipped in the chunk.
+    /// Known words are cut out of the text before it is sent when morphology
+    /// is loaded, otherwise they are filtered out of the answer.
+    async fn extract_words_from_chunk(
+        &self,
+        user_login: &str,
+        text: &str,
+        bypass_cache: bool,
+        known: Option<&DuplicateIndex>,
+    ) -> Result<(Vec<ExtractedWord>, Vec<String>)> {
+        let Some(known) = known else {
+            let words = self
+                .extract_chunk_words(user_login, text, bypass_cache)
+                .await?;
+            return Ok((words, vec![]));
+        };
+
+        if let Some(segments) = self.dictionary.segment(text) {
+            let (remainder, skipped) = chunk::drop_known_words(segments, known);
+            let words = match remainder {
+                Some(remainder) => {
+                    self.extract_chunk_words(user_login, &remainder, bypass_cache)
+                        .await?
+                }
+                None => vec![],
+            };
+            return Ok((words, skipped));
+        }
+
+        let mut words = self
+            .extract_chunk_words(user_login, text, bypass_cache)
+            .await?;
+        let mut skipped = vec![];
+        words.retain(|x| {
+            let is_known = known.contains(&x.word, x.reading.as_deref());
+            if is_known && !skipped.contains(&x.word) {
+                skipped.push(x.word.clone());
+            }
+            !is_known
+        });
+        Ok((words, skipped))
+    }
+
+    async fn extract_chunk_words(
+        &self,
+        user_login: &str,
+        text: &str,
+        bypass_cache: bool,
+    ) -> Result<Vec<ExtractedWord>> {
         let prompt = self
             .config
             .prompts
             .extract_words_from_text
-            .replace("{text}", &text);
-
-        let response: WordsResponse = self.llm_service.send_request(&prompt, 0.1).await?;
+            .replace("{text}", text);
+        let response: WordsResponse = match self
+            .llm(
+                user_login,
+                UsageEndpoint::ExtractWordsFromText,
+                bypass_cache,
+            )
+            .send_request(&prompt, 0.1)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if self.dictionary.is_loaded() && !e.is::<QuotaExceeded>() => {
+                warn!("LLM extraction failed, using dictionary instead: {}", e);
+                let words = self.dictionary.extract_words(text);
+                info!("Extracted {} words from chunk with dictionary", words.len());
+                return Ok(words);
+            }
+            Err(e) => return Err(e),
+        };
 
-        info!(
-            "Successfully extracted {} words from text",
-            response.words.len()
-        );
-        Ok(response.words)
+        info!("Extracted {} words from chunk", response.words.len());
+        Ok(self.dictionary.annotate(response.words))
     }
 
-    #[instrument(skip(self, image_data))]
+    #[instrument(skip(self, image_data, progress))]
     pub async fn extract_words_from_image(
         &self,
+        user_login: &str,
         image_data: Vec<u8>,
+        bypass_cache: bool,
+        progress: Option<&WordsProgress<'_>>,
     ) -> Result<Vec<ExtractedWord>> {
         info!("Extracting words from image");
         let prompt = &self.config.prompts.extract_words_from_image;
 
         let response: WordsResponse = self
-            .llm_service
+            .llm(
+                user_login,
+                UsageEndpoint::ExtractWordsFromImage,
+                bypass_cache,
+            )
             .send_image_request(prompt, &image_data, 0.1)
             .await?;
 
@@ -68,7 +421,11 @@ impl SetService {
             "Successfully extracted {} words from image",
             response.words.len()
         );
-        Ok(response.words)
+        let words = self.dictionary.annotate(response.words);
+        if let Some(progress) = progress {
+            progress(&words);
+        }
+        Ok(words)
     }
 
     #[instrument(skip(self, words), fields(user_login = %user_login))]
@@ -77,41 +434,28 @@ impl SetService {
         user_login: &str,
         words: Vec<ExtractedWord>,
         skip_uniq: bool,
-    ) -> Result<()> {
+    ) -> Result<usize> {
         info!("Saving {} words for user {}", words.len(), user_login);
         if words.is_empty() {
             info!("No words to save for user {}", user_login);
-            return Ok(());
+            return Ok(0);
         }
 
-        let unique_words: HashSet<ExtractedWord> = match skip_uniq {
-            true => words.into_iter().collect(),
-            false => {
-                let all_sets = self.set_repository.list_all(user_login).await?;
-                let mut existing_words = self
-                    .release_repository
-                    .list_all_words(user_login)
-                    .await?
-                    .iter()
-                    .map(|x| x.word().to_owned())
-                    .collect::<HashSet<_>>();
-
-                for set in all_sets {
-                    for card in set.words() {
-                        existing_words.insert(card.word().to_string());
-                    }
-                }
-
-                words
-                    .into_iter()
-                    .filter(|word| !existing_words.contains(&word.word))
-                    .collect()
-            }
+        let mut existing_words = match skip_uniq {
+            true => DuplicateIndex::default(),
+            false => self.known_words(user_login).await?,
         };
+        let mut unique_words = vec![];
+        for (word, part_of_speech) in words.into_iter().map(|x| self.dictionary.normalize(x)) {
+            if !existing_words.contains(&word.word, word.reading.as_deref()) {
+                existing_words.insert(&word.word, word.reading.as_deref());
+                unique_words.push((word, part_of_speech));
+            }
+        }
 
         if unique_words.is_empty() {
             info!("No unique words to save for user {}", user_login);
-            return Ok(());
+            return Ok(0);
         }
 
         info!(
@@ -120,13 +464,29 @@ impl SetService {
             user_login
         );
 
+        let cards = unique_words
+            .into_iter()
+            .map(|(word, part_of_speech)| {
+                WordCard::new(word.word, word.translation, word.reading, part_of_speech)
+            })
+            .collect::<Vec<_>>();
+        let saved = cards.len();
+        self.add_to_tobe_sets(user_login, cards).await?;
+        info!("Successfully saved all words for user {}", user_login);
+        Ok(saved)
+    }
+
+    /// Appends cards to the latest unnamed set that is not started yet,
+    /// opening new sets as they fill up.
+    async fn add_to_tobe_sets(&self, user_login: &str, cards: Vec<WordCard>) -> Result<()> {
+        let settings = self.settings_repository.load(user_login).await?;
         let mut current_ids = self
             .set_repository
             .list_all(user_login)
             .await?
             .into_iter()
             .filter_map(|x| {
-                if x.state() == &LearnSetState::Tobe {
+                if x.state() == &LearnSetState::Tobe && x.name().is_none() {
                     Some(x.id().to_owned())
                 } else {
                     None
@@ -144,7 +504,7 @@ impl SetService {
             info!("Loading existing set {} for user {}", latest_id, user_login);
             let set = self.set_repository.load(user_login, &latest_id).await?;
 
-            if set.is_writabe() {
+            if set.is_writabe(&settings) {
                 set
             } else {
                 info!(
@@ -155,8 +515,8 @@ impl SetService {
             }
         };
 
-        for word_data in unique_words {
-            if !current_set.is_writabe() {
+        for card in cards {
+            if !current_set.is_writabe(&settings) {
                 info!(
                     "Saving current set and creating new one for user {}",
                     user_login
@@ -165,12 +525,11 @@ impl SetService {
                 current_set = LearnSet::new();
             }
 
-            current_set.push(word_data.word, word_data.translation)?;
+            current_set.insert(card, &settings)?;
         }
 
         info!("Saving final set for user {}", user_login);
         self.set_repository.save(user_login, &current_set).await?;
-        info!("Successfully saved all words for user {}", user_login);
         Ok(())
     }
 
@@ -180,19 +539,179 @@ impl SetService {
             "Move to next iter set {} as current for user {}",
             set_id, user_login
         );
-        let mut card_set = self.set_repository.load(user_login, set_id).await?;
+        self.review_set_with(user_login, set_id, HashMap::new())
+            .await?;
 
-        let release = card_set.iter();
-        if let Some(release) = release {
-            self.set_repository.remove(user_login, set_id).await?;
+        info!(
+            "Successfully moved to next set {} as current for user {}",
+            set_id, user_login
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self, grades), fields(user_login = %user_login, set_id = %set_id))]
+    pub async fn review_set(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        grades: HashMap<String, ReviewGrade>,
+    ) -> Result<()> {
+        info!(
+            "Reviewing set {} with {} graded words for user {}",
+            set_id,
+            grades.len(),
+            user_login
+        );
+        self.review_set_with(user_login, set_id, grades).await?;
+
+        info!(
+            "Successfully reviewed set {} for user {}",
+            set_id, user_login
+        );
+        Ok(())
+    }
+
+    async fn review_set_with(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        grades: HashMap<String, ReviewGrade>,
+    ) -> Result<()> {
+        let settings = self.settings_repository.load(user_login).await?;
+        let mut card_set = self.load_set(user_login, set_id).await?;
+        let state_before = card_set.state().clone();
+        let words = card_set.words().to_vec();
+        let new_sets = usize::from(state_before == LearnSetState::Tobe);
+        self.check_daily_limits(user_login, &settings, new_sets, words.len())
+            .await?;
+
+        let ratings = grades
+            .iter()
+            .map(|(id, grade)| (id.clone(), grade.rating))
+            .collect();
+        let release = card_set
+            .review(&self.scheduler(&settings), &settings, &ratings)
+            .map_err(|e| WordError::NotFound(e.to_string()))?;
+
+        let now = Utc::now();
+        let entries = words
+            .iter()
+            .map(|word| {
+                let grade = grades
+                    .get(word.id())
+                    .copied()
+                    .unwrap_or(ReviewGrade::new(Rating::Good));
+                let released = release.iter().any(|x| x.id() == word.id());
+                ReviewLogEntry::new(
+                    word.id().to_owned(),
+                    word.word().to_owned(),
+                    Some(set_id.to_owned()),
+                    now,
+                    grade,
+                    Some(state_before.clone()),
+                    (!released).then(|| card_set.state().clone()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if !release.is_empty() {
+            info!(
+                "Releasing {} words from set {} for user {}",
+                release.len(),
+                set_id,
+                user_login
+            );
             self.release_repository.save(user_login, &release).await?;
+        }
+
+        if card_set.words().is_empty() {
+            self.set_repository.remove(user_login, set_id).await?;
         } else {
             self.set_repository.save(user_login, &card_set).await?;
         }
+        if state_before == LearnSetState::Tobe {
+            self.set_start_repository
+                .append(user_login, set_id, now)
+                .await?;
+        }
 
+        self.review_log_repository
+            .append(user_login, &entries)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(skip(self, grades), fields(user_login = %user_login))]
+    pub async fn review_released(
+        &self,
+        user_login: &str,
+        grades: HashMap<String, ReviewGrade>,
+    ) -> Result<()> {
         info!(
-            "Successfully moved to next set {} as current for user {}",
-            set_id, user_login
+            "Reviewing {} released words for user {}",
+            grades.len(),
+            user_login
+        );
+        let settings = self.settings_repository.load(user_login).await?;
+        self.check_daily_limits(user_login, &settings, 0, grades.len())
+            .await?;
+        let scheduler = self.scheduler(&settings);
+
+        let word_ids = grades.keys().cloned().collect::<Vec<_>>();
+        check_released_word_ids(&word_ids)?;
+        let cards = self
+            .release_repository
+            .load_word_by_ids(user_login, &word_ids)
+            .await?;
+        if let Some(id) = word_ids
+            .iter()
+            .find(|id| cards.iter().all(|x| x.id() != id.as_str()))
+        {
+            return Err(WordError::NotFound(format!("Released word {id} not found")).into());
+        }
+
+        let now = Utc::now();
+        let mut forgotten = vec![];
+        let mut entries = vec![];
+        for mut card in cards {
+            let grade = grades[card.id()];
+            let forgot = grade.rating == Rating::Again;
+            entries.push(ReviewLogEntry::new(
+                card.id().to_owned(),
+                card.word().to_owned(),
+                None,
+                now,
+                grade,
+                None,
+                forgot.then_some(LearnSetState::Tobe),
+            ));
+
+            card.review(&scheduler, grade.rating, now);
+            if forgot {
+                forgotten.push(card);
+                continue;
+            }
+
+            self.release_repository
+                .update_word(user_login, &card)
+                .await?;
+        }
+
+        if !forgotten.is_empty() {
+            info!(
+                "Returning {} forgotten words to learning for user {}",
+                forgotten.len(),
+                user_login
+            );
+            self.return_to_learning(user_login, forgotten).await?;
+        }
+
+        self.review_log_repository
+            .append(user_login, &entries)
+            .await?;
+        info!(
+            "Successfully reviewed released words for user {}",
+            user_login
         );
         Ok(())
     }
@@ -204,24 +723,574 @@ impl SetService {
             word_ids.len(),
             user_login
         );
-        let extracted_words = self
+        check_released_word_ids(&word_ids)?;
+        let cards = self
             .release_repository
             .load_word_by_ids(user_login, &word_ids)
-            .await?
+            .await?;
+        self.return_to_learning(user_login, cards).await?;
+
+        info!("Successfully marked words as tobe for user {}", user_login);
+        Ok(())
+    }
+
+    /// Moves released cards back into Tobe sets with their ids and
+    /// schedules, so the review log still points at them.
+    async fn return_to_learning(&self, user_login: &str, cards: Vec<WordCard>) -> Result<()> {
+        let word_ids = cards.iter().map(|x| x.id().to_owned()).collect::<Vec<_>>();
+        let cards = cards
             .into_iter()
-            .map(|word| ExtractedWord {
-                word: word.word().to_owned(),
-                translation: word.translation().to_owned(),
+            .map(|mut card| {
+                card.unrelease();
+                card
             })
             .collect();
 
-        self.save_extracted_words(user_login, extracted_words, true)
-            .await?;
+        self.add_to_tobe_sets(user_login, cards).await?;
         self.release_repository
             .remove_word_by_ids(user_login, &word_ids)
+            .await
+    }
+
+    /// Packs every set and released word of the user into an Anki `.apkg`.
+    #[instrument(skip(self), fields(user_login = %user_login))]
+    pub async fn export_apkg(&self, user_login: &str) -> Result<Vec<u8>> {
+        info!("Exporting Anki package for user {}", user_login);
+        let mut notes = vec![];
+        for set in self.set_repository.list_all(user_login).await? {
+            let state = serde_json::to_value(set.state())?
+                .as_str()
+                .map(str::to_owned);
+            for card in set.words() {
+                notes.push(AnkiNote {
+                    guid: card.id().to_owned(),
+                    word: card.word().to_owned(),
+                    reading: Some(card.reading()),
+                    translation: card.translation().to_owned(),
+                    state: state.clone(),
+                });
+            }
+        }
+
+        for card in self.release_repository.list_all_words(user_login).await? {
+            notes.push(AnkiNote {
+                guid: card.id().to_owned(),
+                word: card.word().to_owned(),
+                reading: Some(card.reading()),
+                translation: card.translation().to_owned(),
+                state: Some(RELEASED_STATE.to_owned()),
+            });
+        }
+
+        let count = notes.len();
+        let package = tokio::task::spawn_blocking(move || anki::write_package(&notes)).await??;
+        info!("Exported {} notes for user {}", count, user_login);
+        Ok(package)
+    }
+
+    /// Adds words from an Anki package or delimited text file to the Tobe
+    /// sets, skipping words the user already has.
+    #[instrument(skip(self, content, mapping), fields(user_login = %user_login))]
+    pub async fn import_words(
+        &self,
+        user_login: &str,
+        format: ImportFormat,
+        mapping: FieldMapping,
+        content: Vec<u8>,
+    ) -> Result<ImportReport> {
+        info!("Importing {:?} file for user {}", format, user_login);
+        let (words, mut report) =
+            tokio::task::spawn_blocking(move || import::parse(format, &content, &mapping))
+                .await?
+                .map_err(|e| WordError::Invalid(e.to_string()))?;
+
+        let parsed = words.len();
+        report.imported = self.save_extracted_words(user_login, words, false).await?;
+        report.duplicates += parsed - report.imported;
+
+        info!(
+            "Imported {} words for user {}, {} skipped, {} duplicates",
+            report.imported, user_login, report.skipped, report.duplicates
+        );
+        Ok(report)
+    }
+
+    async fn load_set(&self, user_login: &str, set_id: &str) -> Result<LearnSet> {
+        let ids = self.set_repository.list_ids(user_login).await?;
+        if !ids.iter().any(|x| x == set_id) {
+            return Err(WordError::NotFound(format!("Set {set_id} not found")).into());
+        }
+        self.set_repository.load(user_login, set_id).await
+    }
+
+    /// Adds a single word to a set that has not been started yet.
+    #[instrument(skip(self, word), fields(user_login = %user_login))]
+    pub async fn add_word(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        word: ExtractedWord,
+    ) -> Result<WordCard> {
+        if word.word.trim().is_empty() || word.translation.trim().is_empty() {
+            return Err(WordError::Invalid("Word and translation are required".to_owned()).into());
+        }
+        let settings = self.settings_repository.load(user_login).await?;
+        let mut set = self.load_set(user_login, set_id).await?;
+        let (word, part_of_speech) = self.dictionary.normalize(word);
+        let card = WordCard::new(word.word, word.translation, word.reading, part_of_speech);
+        set.insert(card.clone(), &settings)
+            .map_err(|e| WordError::Invalid(format!("Set {set_id}: {e}")))?;
+        self.set_repository.save(user_login, &set).await?;
+        info!("Added word {} to set {}", card.id(), set_id);
+        Ok(card)
+    }
+
+    #[instrument(skip(self, edit), fields(user_login = %user_login))]
+    pub async fn edit_set_word(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        word_id: &str,
+        edit: WordEdit,
+    ) -> Result<WordCard> {
+        let mut set = self.load_set(user_login, set_id).await?;
+        let card = set
+            .word_mut(word_id)
+            .ok_or_else(|| WordError::NotFound(format!("Word {word_id} not found in set")))?;
+        card.edit(edit)
+            .map_err(|e| WordError::Invalid(e.to_string()))?;
+        let card = card.clone();
+        self.set_repository.save(user_login, &set).await?;
+        info!("Edited word {} in set {}", word_id, set_id);
+        Ok(card)
+    }
+
+    /// Removes a word from a set; a set left without words is removed too.
+    #[instrument(skip(self), fields(user_login = %user_login))]
+    pub async fn delete_set_word(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        word_id: &str,
+    ) -> Result<()> {
+        let mut set = self.load_set(user_login, set_id).await?;
+        set.remove_word(word_id)
+            .ok_or_else(|| WordError::NotFound(format!("Word {word_id} not found in set")))?;
+        if set.words().is_empty() {
+            self.set_repository.remove(user_login, set_id).await?;
+            info!("Removed set {} with its last word {}", set_id, word_id);
+        } else {
+            self.set_repository.save(user_login, &set).await?;
+            info!("Removed word {} from set {}", word_id, set_id);
+        }
+        Ok(())
+    }
+
+    /// Moves a word between sets that have not been started yet, so no
+    /// learning progress is carried into a different set.
+    #[instrument(skip(self), fields(user_login = %user_login))]
+    pub async fn move_word(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        word_id: &str,
+        target_set_id: &str,
+    ) -> Result<()> {
+        if set_id == target_set_id {
+            return Ok(());
+        }
+        let settings = self.settings_repository.load(user_login).await?;
+        let mut source = self.load_set(user_login, set_id).await?;
+        let mut target = self.load_set(user_login, target_set_id).await?;
+        if source.state() != &LearnSetState::Tobe {
+            return Err(
+                WordError::Invalid(format!("Set {set_id} is already being learned")).into(),
+            );
+        }
+
+        let card = source
+            .remove_word(word_id)
+            .ok_or_else(|| WordError::NotFound(format!("Word {word_id} not found in set")))?;
+        target
+            .insert(card, &settings)
+            .map_err(|e| WordError::Invalid(format!("Set {target_set_id}: {e}")))?;
+
+        self.set_repository.save(user_login, &target).await?;
+        if source.words().is_empty() {
+            self.set_repository.remove(user_login, set_id).await?;
+        } else {
+            self.set_repository.save(user_login, &source).await?;
+        }
+        info!(
+            "Moved word {} from set {} to {}",
+            word_id, set_id, target_set_id
+        );
+        Ok(())
+    }
+
+    async fn load_released_word(&self, user_login: &str, word_id: &str) -> Result<WordCard> {
+        check_released_word_ids(&[word_id.to_owned()])?;
+        self.release_repository
+            .load_word_by_ids(user_login, &[word_id.to_owned()])
+            .await?
+            .pop()
+            .ok_or_else(|| WordError::NotFound(format!("Released word {word_id} not found")).into())
+    }
+
+    #[instrument(skip(self, edit), fields(user_login = %user_login))]
+    pub async fn edit_released_word(
+        &self,
+        user_login: &str,
+        word_id: &str,
+        edit: WordEdit,
+    ) -> Result<WordCard> {
+        let mut card = self.load_released_word(user_login, word_id).await?;
+        card.edit(edit)
+            .map_err(|e| WordError::Invalid(e.to_string()))?;
+        self.release_repository
+            .update_word(user_login, &card)
             .await?;
+        info!("Edited released word {}", word_id);
+        Ok(card)
+    }
 
-        info!("Successfully marked words as tobe for user {}", user_login);
+    #[instrument(skip(self), fields(user_login = %user_login))]
+    pub async fn delete_released_word(&self, user_login: &str, word_id: &str) -> Result<()> {
+        self.load_released_word(user_login, word_id).await?;
+        self.release_repository
+            .remove_word(user_login, word_id)
+            .await?;
+        info!("Removed released word {}", word_id);
         Ok(())
     }
+
+    #[instrument(skip(self), fields(user_login = %user_login))]
+    pub async fn create_set(&self, user_login: &str, name: String) -> Result<LearnSet> {
+        let set = LearnSet::named(name);
+        if set.name().is_none() {
+            return Err(WordError::Invalid("Set name is required".to_owned()).into());
+        }
+        self.set_repository.save(user_login, &set).await?;
+        info!("Created set {} for user {}", set.id(), user_login);
+        Ok(set)
+    }
+
+    #[instrument(skip(self), fields(user_login = %user_login))]
+    pub async fn delete_set(&self, user_login: &str, set_id: &str) -> Result<()> {
+        self.load_set(user_login, set_id).await?;
+        self.set_repository.remove(user_login, set_id).await?;
+        info!("Removed set {} of user {}", set_id, user_login);
+        Ok(())
+    }
+
+    /// Moves the words of `source_set_id` into `set_id` and removes the source.
+    #[instrument(skip(self), fields(user_login = %user_login))]
+    pub async fn merge_sets(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        source_set_id: &str,
+    ) -> Result<()> {
+        if set_id == source_set_id {
+            return Err(WordError::Invalid("A set cannot be merged into itself".to_owned()).into());
+        }
+        let settings = self.settings_repository.load(user_login).await?;
+        let mut set = self.load_set(user_login, set_id).await?;
+        let source = self.load_set(user_login, source_set_id).await?;
+        set.merge(source, &settings)
+            .map_err(|e| WordError::Invalid(e.to_string()))?;
+        self.set_repository.save(user_login, &set).await?;
+        self.set_repository
+            .remove(user_login, source_set_id)
+            .await?;
+        info!("Merged set {} into {}", source_set_id, set_id);
+        Ok(())
+    }
+
+    /// Moves the given words into a new set and returns it.
+    #[instrument(skip(self, word_ids), fields(user_login = %user_login))]
+    pub async fn split_set(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        word_ids: Vec<String>,
+        name: Option<String>,
+    ) -> Result<LearnSet> {
+        let mut set = self.load_set(user_login, set_id).await?;
+        let other = set
+            .split(&word_ids, name)
+            .map_err(|e| WordError::Invalid(e.to_string()))?;
+        self.set_repository.save(user_login, &other).await?;
+        self.set_repository.save(user_login, &set).await?;
+        info!(
+            "Split {} words of set {} into {}",
+            other.words().len(),
+            set_id,
+            other.id()
+        );
+        Ok(other)
+    }
+
+    #[instrument(skip(self, word_ids), fields(user_login = %user_login))]
+    pub async fn reorder_set(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        word_ids: Vec<String>,
+    ) -> Result<()> {
+        let mut set = self.load_set(user_login, set_id).await?;
+        set.reorder(&word_ids)
+            .map_err(|e| WordError::Invalid(e.to_string()))?;
+        self.set_repository.save(user_login, &set).await?;
+        info!("Reordered words of set {}", set_id);
+        Ok(())
+    }
+
+    /// Starts learning a set that has not been started yet, within the
+    /// user's daily limit of new sets.
+    #[instrument(skip(self), fields(user_login = %user_login))]
+    pub async fn start_set(&self, user_login: &str, set_id: &str) -> Result<()> {
+        let settings = self.settings_repository.load(user_login).await?;
+        let mut set = self.load_set(user_login, set_id).await?;
+        self.check_daily_limits(user_login, &settings, 1, 0).await?;
+        let now = Utc::now();
+        set.start(now)
+            .map_err(|e| WordError::Invalid(e.to_string()))?;
+        self.set_repository.save(user_login, &set).await?;
+        self.set_start_repository
+            .append(user_login, set_id, now)
+            .await?;
+        info!("Started set {} for user {}", set_id, user_login);
+        Ok(())
+    }
+}
+
+/// The file backend builds paths from word ids, so an id that is not a ULID
+/// could reach files outside the user's directory.
+fn check_released_word_ids(ids: &[String]) -> Result<()> {
+    match ids.iter().find(|x| Ulid::from_string(x).is_err()) {
+        Some(id) => Err(WordError::NotFound(format!("Released word {id} not found")).into()),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::DictionaryConfig,
+        llm::mock::Fixture,
+        test_support::{
+            USER, fixture, morphology_dictionary, set_service, set_service_with_dictionary,
+        },
+    };
+    use serde_json::json;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn words_fixture(contains: &str, words: &[(&str, &str)]) -> Fixture {
+        let words = words
+            .iter()
+            .map(|(word, translation)| json!({ "word": word, "translation": translation }))
+            .collect::<Vec<_>>();
+        fixture(contains, None, json!({ "words": words }))
+    }
+
+    async fn service_with_morphology(dir: &Path, fixtures: Vec<Fixture>) -> SetService {
+        let config = DictionaryConfig {
+            index_path: dir.join("jmdict.json").to_string_lossy().into_owned(),
+            kanji_index_path: dir.join("kanjidic.json").to_string_lossy().into_owned(),
+            morphology_path: morphology_dictionary(dir),
+            ..DictionaryConfig::default()
+        };
+        let dictionary = DictionaryService::load(&config).await.unwrap();
+        set_service_with_dictionary(dir, fixtures, dictionary).await
+    }
+
+    async fn add_words(service: &SetService, words: &[&str]) {
+        let cards = words
+            .iter()
+            .map(|x| WordCard::new(x.to_string(), "t".to_owned(), None, None))
+            .collect();
+        service.add_to_tobe_sets(USER, cards).await.unwrap();
+    }
+
+    fn words(extraction: &TextExtraction) -> Vec<&str> {
+        extraction.words.iter().map(|x| x.word.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn extracts_words_answered_by_the_model() {
+        let dir = TempDir::new().unwrap();
+        let service = set_service(
+            dir.path(),
+            vec![words_fixture(
+                "猫が好き",
+                &[("猫", "кошка"), ("好き", "нравиться")],
+            )],
+        )
+        .await;
+
+        let extraction = service
+            .extract_words_from_text(USER, "猫が好き".to_owned(), false, false, None)
+            .await
+            .unwrap();
+
+        assert_eq!(words(&extraction), ["猫", "好き"]);
+        assert!(extraction.known_words.is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_words_already_in_sets() {
+        let dir = TempDir::new().unwrap();
+        let service = set_service(
+            dir.path(),
+            vec![words_fixture(
+                "猫が好き",
+                &[("猫", "кошка"), ("好き", "нравиться")],
+            )],
+        )
+        .await;
+        service
+            .add_to_tobe_sets(
+                USER,
+                vec![WordCard::new(
+                    "猫".to_owned(),
+                    "кошка".to_owned(),
+                    None,
+                    None,
+                )],
+            )
+            .await
+            .unwrap();
+
+        let extraction = service
+            .extract_words_from_text(USER, "猫が好き".to_owned(), false, true, None)
+            .await
+            .unwrap();
+
+        assert_eq!(words(&extraction), ["好き"]);
+        assert_eq!(extraction.known_words, ["猫"]);
+    }
+
+    #[tokio::test]
+    async fn cuts_known_words_out_of_the_prompt() {
+        let dir = TempDir::new().unwrap();
+        let service = service_with_morphology(
+            dir.path(),
+            vec![words_fixture(
+                "Words of: 勉強した。",
+                &[("勉強する", "учиться")],
+            )],
+        )
+        .await;
+        add_words(&service, &["猫"]).await;
+
+        let extraction = service
+            .extract_words_from_text(USER, "猫が勉強した。".to_owned(), false, true, None)
+            .await
+            .unwrap();
+
+        assert_eq!(words(&extraction), ["勉強する"]);
+        assert_eq!(extraction.known_words, ["猫"]);
+    }
+
+    #[tokio::test]
+    async fn does_not_ask_the_model_when_every_word_is_known() {
+        let dir = TempDir::new().unwrap();
+        let service = service_with_morphology(dir.path(), vec![]).await;
+        add_words(&service, &["猫", "勉強した"]).await;
+
+        let extraction = service
+            .extract_words_from_text(USER, "猫が勉強した。".to_owned(), false, true, None)
+            .await
+            .unwrap();
+
+        assert!(extraction.words.is_empty());
+        assert_eq!(extraction.known_words, ["猫", "勉強する"]);
+    }
+
+    #[tokio::test]
+    async fn released_word_ids_cannot_leave_the_user_directory() {
+        let dir = TempDir::new().unwrap();
+        let service = set_service(dir.path(), vec![]).await;
+        let card = WordCard::new("猫".to_owned(), "кошка".to_owned(), None, None);
+        service
+            .release_repository
+            .save("other", std::slice::from_ref(&card))
+            .await
+            .unwrap();
+        let id = format!("../other/{}", card.id());
+
+        let edit = WordEdit {
+            translation: Some("cat".to_owned()),
+            ..WordEdit::default()
+        };
+        let edited = service.edit_released_word(USER, &id, edit).await;
+        let deleted = service.delete_released_word(USER, &id).await;
+
+        for error in [edited.unwrap_err(), deleted.unwrap_err()] {
+            assert!(matches!(
+                error.downcast_ref::<WordError>(),
+                Some(WordError::NotFound(_))
+            ));
+        }
+        let kept = service
+            .release_repository
+            .load_word("other", card.id())
+            .await
+            .unwrap();
+        assert_eq!(kept.translation(), "кошка");
+    }
+
+    async fn set_with_word(service: &SetService, word: &str) -> String {
+        let set = service.create_set(USER, word.to_owned()).await.unwrap();
+        let word = ExtractedWord {
+            word: word.to_owned(),
+            translation: "t".to_owned(),
+            reading: None,
+        };
+        service.add_word(USER, set.id(), word).await.unwrap();
+        set.id().to_owned()
+    }
+
+    #[tokio::test]
+    async fn removed_sets_still_count_towards_the_new_set_limit() {
+        let dir = TempDir::new().unwrap();
+        let service = set_service(dir.path(), vec![]).await;
+        let settings = LearningSettings {
+            max_new_sets_per_day: Some(1),
+            ..LearningSettings::default()
+        };
+        service
+            .settings_repository
+            .save(USER, &settings)
+            .await
+            .unwrap();
+        let first = set_with_word(&service, "猫").await;
+        let second = set_with_word(&service, "犬").await;
+
+        service.start_set(USER, &first).await.unwrap();
+        service.delete_set(USER, &first).await.unwrap();
+        let error = service.start_set(USER, &second).await.unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<WordError>(),
+            Some(WordError::LimitReached(_))
+        ));
+        let second = service.load_set(USER, &second).await.unwrap();
+        assert_eq!(second.state(), &LearnSetState::Tobe);
+    }
+
+    #[tokio::test]
+    async fn fails_when_the_model_has_no_answer() {
+        let dir = TempDir::new().unwrap();
+        let service = set_service(dir.path(), vec![]).await;
+
+        let result = service
+            .extract_words_from_text(USER, "猫が好き".to_owned(), false, false, None)
+            .await;
+
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,191 @@
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::storage::SqliteStorage;
+
+const STORAGE_DIR: &str = "set_starts";
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SetStart {
+    set_id: String,
+    started_at: DateTime<Utc>,
+}
+
+/// Append-only record of started sets. Sets can be removed or merged after
+/// they were started, so the daily limit of new sets is counted from here.
+#[async_trait]
+pub trait SetStartRepository: Send + Sync {
+    async fn append(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        started_at: DateTime<Utc>,
+    ) -> anyhow::Result<()>;
+
+    async fn count_since(&self, user_login: &str, from: DateTime<Utc>) -> anyhow::Result<usize>;
+}
+
+#[derive(Clone)]
+pub struct FileSetStartRepository {
+    storage_dir: PathBuf,
+}
+
+impl FileSetStartRepository {
+    pub async fn new(data_dir: &Path) -> anyhow::Result<Self> {
+        let storage_dir = data_dir.join(STORAGE_DIR);
+        fs::create_dir_all(&storage_dir).await?;
+
+        Ok(Self { storage_dir })
+    }
+
+    fn get_user_path(&self, user_login: &str) -> PathBuf {
+        self.storage_dir.join(format!("{user_login}.jsonl"))
+    }
+}
+
+#[async_trait]
+impl SetStartRepository for FileSetStartRepository {
+    async fn append(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        started_at: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        let start = SetStart {
+            set_id: set_id.to_owned(),
+            started_at,
+        };
+        let mut line = serde_json::to_string(&start)?;
+        line.push('\n');
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.get_user_path(user_login))
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn count_since(&self, user_login: &str, from: DateTime<Utc>) -> anyhow::Result<usize> {
+        let file_path = self.get_user_path(user_login);
+        if !file_path.exists() {
+            return Ok(0);
+        }
+
+        let content = fs::read_to_string(file_path).await?;
+        let starts = content
+            .lines()
+            .map(serde_json::from_str::<SetStart>)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(starts.iter().filter(|x| x.started_at >= from).count())
+    }
+}
+
+#[derive(Clone)]
+pub struct SqliteSetStartRepository {
+    storage: SqliteStorage,
+}
+
+impl SqliteSetStartRepository {
+    pub async fn new(storage: SqliteStorage) -> anyhow::Result<Self> {
+        storage
+            .call(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS set_starts (
+                        user_login TEXT NOT NULL,
+                        set_id TEXT NOT NULL,
+                        started_at TEXT NOT NULL
+                    );
+                    CREATE INDEX IF NOT EXISTS set_starts_user_started_at
+                        ON set_starts (user_login, started_at);",
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(Self { storage })
+    }
+}
+
+#[async_trait]
+impl SetStartRepository for SqliteSetStartRepository {
+    async fn append(
+        &self,
+        user_login: &str,
+        set_id: &str,
+        started_at: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        let user_login = user_login.to_owned();
+        let set_id = set_id.to_owned();
+        let started_at = format_timestamp(started_at);
+        self.storage
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO set_starts (user_login, set_id, started_at)
+                     VALUES (?1, ?2, ?3)",
+                    params![user_login, set_id, started_at],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn count_since(&self, user_login: &str, from: DateTime<Utc>) -> anyhow::Result<usize> {
+        let user_login = user_login.to_owned();
+        let from = format_timestamp(from);
+        self.storage
+            .call(move |conn| {
+                let count = conn.query_row(
+                    "SELECT COUNT(*) FROM set_starts
+                     WHERE user_login = ?1 AND started_at >= ?2",
+                    params![user_login, from],
+                    |row| row.get::<_, i64>(0),
+                )?;
+                Ok(count as usize)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn counts_starts_from_the_given_time() {
+        let dir = TempDir::new().unwrap();
+        let storage = SqliteStorage::open(&dir.path().join("db.sqlite").to_string_lossy())
+            .await
+            .unwrap();
+        let repositories: [Box<dyn SetStartRepository>; 2] = [
+            Box::new(FileSetStartRepository::new(dir.path()).await.unwrap()),
+            Box::new(SqliteSetStartRepository::new(storage).await.unwrap()),
+        ];
+        let now = Utc::now();
+
+        for repository in repositories {
+            repository
+                .append("user", "old", now - Duration::days(1))
+                .await
+                .unwrap();
+            repository.append("user", "new", now).await.unwrap();
+            repository.append("other", "new", now).await.unwrap();
+
+            let from = now - Duration::hours(1);
+            assert_eq!(repository.count_since("user", from).await.unwrap(), 1);
+            assert_eq!(repository.count_since("nobody", from).await.unwrap(), 0);
+        }
+    }
+}
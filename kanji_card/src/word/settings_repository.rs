@@ -0,0 +1,115 @@
+use async_trait::async_trait;
+use rusqlite::{OptionalExtension, params};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::{storage::SqliteStorage, word::domain::settings::LearningSettings};
+
+const STORAGE_DIR: &str = "settings";
+
+#[async_trait]
+pub trait LearningSettingsRepository: Send + Sync {
+    /// Saved settings of the user, or the defaults when there are none.
+    async fn load(&self, user_login: &str) -> anyhow::Result<LearningSettings>;
+
+    async fn save(&self, user_login: &str, settings: &LearningSettings) -> anyhow::Result<()>;
+}
+
+#[derive(Clone)]
+pub struct FileLearningSettingsRepository {
+    storage_dir: PathBuf,
+}
+
+impl FileLearningSettingsRepository {
+    pub async fn new(data_dir: &Path) -> anyhow::Result<Self> {
+        let storage_dir = data_dir.join(STORAGE_DIR);
+        fs::create_dir_all(&storage_dir).await?;
+
+        Ok(Self { storage_dir })
+    }
+
+    fn get_user_path(&self, user_login: &str) -> PathBuf {
+        self.storage_dir.join(format!("{user_login}.json"))
+    }
+}
+
+#[async_trait]
+impl LearningSettingsRepository for FileLearningSettingsRepository {
+    async fn load(&self, user_login: &str) -> anyhow::Result<LearningSettings> {
+        let file_path = self.get_user_path(user_login);
+        if !file_path.exists() {
+            return Ok(LearningSettings::default());
+        }
+
+        let json = fs::read_to_string(file_path).await?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    async fn save(&self, user_login: &str, settings: &LearningSettings) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(settings)?;
+        fs::write(self.get_user_path(user_login), json).await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct SqliteLearningSettingsRepository {
+    storage: SqliteStorage,
+}
+
+impl SqliteLearningSettingsRepository {
+    pub async fn new(storage: SqliteStorage) -> anyhow::Result<Self> {
+        storage
+            .call(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS learning_settings (
+                        user_login TEXT PRIMARY KEY NOT NULL,
+                        data TEXT NOT NULL
+                    );",
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(Self { storage })
+    }
+}
+
+#[async_trait]
+impl LearningSettingsRepository for SqliteLearningSettingsRepository {
+    async fn load(&self, user_login: &str) -> anyhow::Result<LearningSettings> {
+        let user_login = user_login.to_owned();
+        let json = self
+            .storage
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT data FROM learning_settings WHERE user_login = ?1",
+                        params![user_login],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .optional()?)
+            })
+            .await?;
+
+        match json {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(LearningSettings::default()),
+        }
+    }
+
+    async fn save(&self, user_login: &str, settings: &LearningSettings) -> anyhow::Result<()> {
+        let json = serde_json::to_string(settings)?;
+        let user_login = user_login.to_owned();
+        self.storage
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO learning_settings (user_login, data) VALUES (?1, ?2)
+                     ON CONFLICT (user_login) DO UPDATE SET data = excluded.data",
+                    params![user_login, json],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+}
@@ -0,0 +1,467 @@
+use axum::{
+    Extension,
+    extract::{Query, State},
+    http::StatusCode,
+    middleware,
+};
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+use tracing::instrument;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    environment::auth::{Claims, JwtConfig, auth_middleware},
+    word::{
+        domain::{schedule::Rating, set::LearnSetState, settings::LearningSettings},
+        review_log_repository::{ReviewLogFilter, ReviewLogRepository},
+        set_repository::LearnSetRepository,
+        settings_repository::LearningSettingsRepository,
+        word_release_repository::WordReleaseRepository,
+    },
+};
+
+const DEFAULT_HEATMAP_DAYS: u64 = 365;
+const FORECAST_DAYS: usize = 30;
+
+#[derive(Clone)]
+struct StatsState {
+    repository: Arc<dyn LearnSetRepository>,
+    release_repository: Arc<dyn WordReleaseRepository>,
+    review_log_repository: Arc<dyn ReviewLogRepository>,
+    settings_repository: Arc<dyn LearningSettingsRepository>,
+}
+
+pub fn stats_router(
+    set_repository: Arc<dyn LearnSetRepository>,
+    release_repository: Arc<dyn WordReleaseRepository>,
+    review_log_repository: Arc<dyn ReviewLogRepository>,
+    settings_repository: Arc<dyn LearningSettingsRepository>,
+    jwt_config: JwtConfig,
+) -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(get_heatmap))
+        .routes(routes!(get_streak))
+        .routes(routes!(get_retention))
+        .routes(routes!(get_weekly_releases))
+        .routes(routes!(get_due_forecast))
+        .layer(middleware::from_fn_with_state(
+            jwt_config.clone(),
+            auth_middleware,
+        ))
+        .with_state(StatsState {
+            repository: set_repository,
+            release_repository,
+            review_log_repository,
+            settings_repository,
+        })
+}
+
+#[derive(Deserialize, Debug)]
+struct HeatmapQuery {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct DailyReviews {
+    date: NaiveDate,
+    reviews: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+struct StreakResponse {
+    current: usize,
+    longest: usize,
+    last_review_date: Option<NaiveDate>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct StateRetention {
+    /// Set state before the review; empty for released words.
+    state: Option<LearnSetState>,
+    reviews: usize,
+    recalled: usize,
+    retention: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+struct WeeklyReleases {
+    week_start: NaiveDate,
+    words: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+struct DailyForecast {
+    date: NaiveDate,
+    set_words: usize,
+    released_words: usize,
+}
+
+#[utoipa::path(
+    get,
+    path = "/heatmap",
+    params(
+        ("from" = Option<NaiveDate>, Query, description = "First day, defaults to one year ago"),
+        ("to" = Option<NaiveDate>, Query, description = "Last day, defaults to today")
+    ),
+    responses(
+        (status = 200, description = "Review counts per day", body = Vec<DailyReviews>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn get_heatmap(
+    State(state): State<StatsState>,
+    Query(params): Query<HeatmapQuery>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<Vec<DailyReviews>>, (StatusCode, String)> {
+    let settings = load_settings(&state, &claims.sub).await?;
+    let today = settings.local_date(Utc::now());
+    let to = params.to.unwrap_or(today);
+    let from = params.from.unwrap_or_else(|| {
+        to.checked_sub_days(Days::new(DEFAULT_HEATMAP_DAYS))
+            .unwrap_or(to)
+    });
+
+    let filter = ReviewLogFilter {
+        card_id: None,
+        from: Some(settings.date_start(from)),
+        to: to.succ_opt().map(|x| settings.date_start(x)),
+    };
+
+    match state.review_log_repository.list(&claims.sub, &filter).await {
+        Ok(entries) => {
+            let mut days = BTreeMap::new();
+            for entry in entries {
+                *days
+                    .entry(settings.local_date(entry.timestamp()))
+                    .or_insert(0) += 1;
+            }
+
+            let result = days
+                .into_iter()
+                .map(|(date, reviews)| DailyReviews { date, reviews })
+                .collect();
+            Ok(axum::Json(result))
+        }
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/streak",
+    responses(
+        (status = 200, description = "Current and longest streak of study days", body = StreakResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn get_streak(
+    State(state): State<StatsState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<StreakResponse>, (StatusCode, String)> {
+    let settings = load_settings(&state, &claims.sub).await?;
+    match state
+        .review_log_repository
+        .list(&claims.sub, &ReviewLogFilter::default())
+        .await
+    {
+        Ok(entries) => {
+            let days = entries
+                .iter()
+                .map(|entry| settings.local_date(entry.timestamp()))
+                .collect::<BTreeSet<_>>();
+
+            Ok(axum::Json(streaks(&days, settings.local_date(Utc::now()))))
+        }
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/retention",
+    responses(
+        (status = 200, description = "Share of recalled answers per learning stage", body = Vec<StateRetention>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn get_retention(
+    State(state): State<StatsState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<Vec<StateRetention>>, (StatusCode, String)> {
+    match state
+        .review_log_repository
+        .list(&claims.sub, &ReviewLogFilter::default())
+        .await
+    {
+        Ok(entries) => {
+            let mut result: Vec<StateRetention> = vec![];
+            for entry in entries {
+                let state = entry.state_before().cloned();
+                let index = match result.iter().position(|x| x.state == state) {
+                    Some(index) => index,
+                    None => {
+                        result.push(StateRetention {
+                            state,
+                            reviews: 0,
+                            recalled: 0,
+                            retention: 0.0,
+                        });
+                        result.len() - 1
+                    }
+                };
+
+                let stats = &mut result[index];
+                stats.reviews += 1;
+                if entry.rating() != Rating::Again {
+                    stats.recalled += 1;
+                }
+            }
+
+            for stats in result.iter_mut() {
+                stats.retention = stats.recalled as f64 / stats.reviews as f64;
+            }
+            result.sort_by_key(|x| x.state.as_ref().map_or(u8::MAX, state_order));
+
+            Ok(axum::Json(result))
+        }
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/releases",
+    responses(
+        (status = 200, description = "Words released per week, weeks start on Monday", body = Vec<WeeklyReleases>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn get_weekly_releases(
+    State(state): State<StatsState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<Vec<WeeklyReleases>>, (StatusCode, String)> {
+    let settings = load_settings(&state, &claims.sub).await?;
+    match state.release_repository.list_all_words(&claims.sub).await {
+        Ok(cards) => {
+            let mut weeks = BTreeMap::new();
+            for release in cards.iter().filter_map(|w| w.release_timestamp()) {
+                let date = settings.local_date(release);
+                let week_start = date
+                    .checked_sub_days(Days::new(date.weekday().num_days_from_monday() as u64))
+                    .unwrap_or(date);
+                *weeks.entry(week_start).or_insert(0) += 1;
+            }
+
+            let result = weeks
+                .into_iter()
+                .map(|(week_start, words)| WeeklyReleases { week_start, words })
+                .collect();
+            Ok(axum::Json(result))
+        }
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/forecast",
+    responses(
+        (status = 200, description = "Words due per day for the next 30 days, overdue words count today", body = Vec<DailyForecast>),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(state, claims))]
+async fn get_due_forecast(
+    State(state): State<StatsState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<axum::Json<Vec<DailyForecast>>, (StatusCode, String)> {
+    let settings = load_settings(&state, &claims.sub).await?;
+    let today = settings.local_date(Utc::now());
+    let mut forecast = (0..FORECAST_DAYS)
+        .map(|day| DailyForecast {
+            date: today
+                .checked_add_days(Days::new(day as u64))
+                .unwrap_or(today),
+            set_words: 0,
+            released_words: 0,
+        })
+        .collect::<Vec<_>>();
+
+    match state.repository.list_all(&claims.sub).await {
+        Ok(sets) => {
+            for set in sets {
+                if set.state() == &LearnSetState::Tobe {
+                    continue;
+                }
+
+                if let Some(day) = set
+                    .time_to_learn(&settings)
+                    .and_then(|x| forecast_day(&settings, today, x))
+                {
+                    forecast[day].set_words += set.words().len();
+                }
+            }
+        }
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+
+    match state.release_repository.list_all_words(&claims.sub).await {
+        Ok(cards) => {
+            for day in cards.iter().filter_map(|w| w.due()) {
+                if let Some(day) = forecast_day(&settings, today, day) {
+                    forecast[day].released_words += 1;
+                }
+            }
+        }
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+
+    Ok(axum::Json(forecast))
+}
+
+/// Days are bucketed on the user's local date, the same day the daily limits
+/// reset on.
+async fn load_settings(
+    state: &StatsState,
+    user_login: &str,
+) -> Result<LearningSettings, (StatusCode, String)> {
+    state
+        .settings_repository
+        .load(user_login)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+fn forecast_day(
+    settings: &LearningSettings,
+    today: NaiveDate,
+    due: DateTime<Utc>,
+) -> Option<usize> {
+    let day = (settings.local_date(due) - today).num_days().max(0) as usize;
+    (day < FORECAST_DAYS).then_some(day)
+}
+
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> StreakResponse {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in days {
+        run = match previous {
+            Some(previous) if previous.succ_opt() == Some(*day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    // A streak is still alive until the end of the day after the last review.
+    let last_review_date = days.last().copied();
+    let current = match last_review_date {
+        Some(last) if last == today || last.succ_opt() == Some(today) => run,
+        _ => 0,
+    };
+
+    StreakResponse {
+        current,
+        longest,
+        last_review_date,
+    }
+}
+
+fn state_order(state: &LearnSetState) -> u8 {
+    match state {
+        LearnSetState::Tobe => 0,
+        LearnSetState::OneDay => 1,
+        LearnSetState::TwoDay => 2,
+        LearnSetState::ThreeDay => 3,
+        LearnSetState::FiveDay => 4,
+        LearnSetState::SevenDay => 5,
+        LearnSetState::TenDay => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        text.parse().unwrap()
+    }
+
+    fn time(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn days(dates: &[&str]) -> BTreeSet<NaiveDate> {
+        dates.iter().map(|x| date(x)).collect()
+    }
+
+    #[test]
+    fn streak_lasts_through_the_day_after_the_last_review() {
+        let studied = days(&["2025-01-01", "2025-01-02", "2025-01-03"]);
+
+        assert_eq!(streaks(&studied, date("2025-01-03")).current, 3);
+        assert_eq!(streaks(&studied, date("2025-01-04")).current, 3);
+        assert_eq!(streaks(&studied, date("2025-01-05")).current, 0);
+        assert_eq!(streaks(&studied, date("2025-01-05")).longest, 3);
+    }
+
+    #[test]
+    fn longest_streak_survives_a_gap() {
+        let studied = days(&["2025-01-01", "2025-01-02", "2025-01-03", "2025-01-05"]);
+        let streak = streaks(&studied, date("2025-01-05"));
+
+        assert_eq!(streak.current, 1);
+        assert_eq!(streak.longest, 3);
+        assert_eq!(streak.last_review_date, Some(date("2025-01-05")));
+        assert_eq!(streaks(&BTreeSet::new(), date("2025-01-05")).longest, 0);
+    }
+
+    #[test]
+    fn forecast_counts_overdue_words_today_and_stops_after_thirty_days() {
+        let settings = LearningSettings::default();
+        let today = date("2025-01-10");
+
+        assert_eq!(
+            forecast_day(&settings, today, time("2025-01-01T12:00:00Z")),
+            Some(0)
+        );
+        assert_eq!(
+            forecast_day(&settings, today, time("2025-01-10T23:59:59Z")),
+            Some(0)
+        );
+        assert_eq!(
+            forecast_day(&settings, today, time("2025-02-08T00:00:00Z")),
+            Some(FORECAST_DAYS - 1)
+        );
+        assert_eq!(
+            forecast_day(&settings, today, time("2025-02-09T00:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn forecast_day_follows_the_utc_offset() {
+        let settings = LearningSettings {
+            utc_offset_minutes: 9 * 60,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            forecast_day(&settings, date("2025-01-10"), time("2025-01-10T16:00:00Z")),
+            Some(1)
+        );
+    }
+}
@@ -1,19 +1,53 @@
 use anyhow::anyhow;
-use std::path::PathBuf;
+use async_trait::async_trait;
+use rusqlite::{OptionalExtension, params};
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
-use crate::word::domain::WordCard;
+use crate::{storage::SqliteStorage, word::domain::WordCard};
 
-const WORD_STORAGE_DIR: &str = "data/release_word";
+const WORD_STORAGE_DIR: &str = "release_word";
+
+#[async_trait]
+pub trait WordReleaseRepository: Send + Sync {
+    async fn remove_word(&self, user_login: &str, card_id: &str) -> anyhow::Result<()>;
+
+    /// Removes all cards or none of them when an id is unknown.
+    async fn remove_word_by_ids(&self, user_login: &str, ids: &[String]) -> anyhow::Result<()>;
+
+    async fn update_word(&self, user_login: &str, card: &WordCard) -> anyhow::Result<()>;
+
+    async fn save(&self, user_login: &str, cards: &[WordCard]) -> anyhow::Result<()>;
+
+    async fn load_word(&self, user_login: &str, id: &str) -> anyhow::Result<WordCard>;
+
+    async fn list_word_ids(&self, user_login: &str) -> anyhow::Result<Vec<String>>;
+
+    async fn load_word_by_ids(
+        &self,
+        user_login: &str,
+        ids: &[String],
+    ) -> anyhow::Result<Vec<WordCard>> {
+        let mut cards = Vec::new();
+        for id in ids {
+            if let Ok(card) = self.load_word(user_login, id).await {
+                cards.push(card);
+            }
+        }
+        Ok(cards)
+    }
+
+    async fn list_all_words(&self, user_login: &str) -> anyhow::Result<Vec<WordCard>>;
+}
 
 #[derive(Clone)]
-pub struct WordReleaseRepository {
+pub struct FileWordReleaseRepository {
     word_storage_dir: PathBuf,
 }
 
-impl WordReleaseRepository {
-    pub async fn new() -> anyhow::Result<Self> {
-        let word_storage_dir = PathBuf::from(WORD_STORAGE_DIR);
+impl FileWordReleaseRepository {
+    pub async fn new(data_dir: &Path) -> anyhow::Result<Self> {
+        let word_storage_dir = data_dir.join(WORD_STORAGE_DIR);
         fs::create_dir_all(&word_storage_dir).await?;
 
         Ok(Self { word_storage_dir })
@@ -22,8 +56,11 @@ impl WordReleaseRepository {
     fn get_word_user_path(&self, user_login: &str) -> PathBuf {
         self.word_storage_dir.join(user_login)
     }
+}
 
-    pub async fn remove_word(&self, user_login: &str, card_id: &str) -> anyhow::Result<()> {
+#[async_trait]
+impl WordReleaseRepository for FileWordReleaseRepository {
+    async fn remove_word(&self, user_login: &str, card_id: &str) -> anyhow::Result<()> {
         let file_path = self
             .get_word_user_path(user_login)
             .join(format!("{card_id}.json"));
@@ -32,14 +69,25 @@ impl WordReleaseRepository {
         Ok(())
     }
 
-    pub async fn remove_word_by_ids(&self, user_login: &str, ids: &[String]) -> anyhow::Result<()> {
-        for id in ids {
-            self.remove_word(user_login, id).await?;
+    async fn remove_word_by_ids(&self, user_login: &str, ids: &[String]) -> anyhow::Result<()> {
+        let user_dir = self.get_word_user_path(user_login);
+        let file_paths = ids
+            .iter()
+            .map(|id| user_dir.join(format!("{id}.json")))
+            .collect::<Vec<_>>();
+        if file_paths.iter().any(|x| !x.exists()) {
+            return Err(anyhow!("Card not found"));
+        }
+
+        // Files are removed one by one; only an I/O failure halfway through
+        // leaves part of them behind, unlike the transactional SQLite backend.
+        for file_path in file_paths {
+            fs::remove_file(file_path).await?;
         }
         Ok(())
     }
 
-    pub async fn update_word(&self, user_login: &str, card: &WordCard) -> anyhow::Result<()> {
+    async fn update_word(&self, user_login: &str, card: &WordCard) -> anyhow::Result<()> {
         let word_json = serde_json::to_string_pretty(card)?;
         let word_dir = self.get_word_user_path(user_login);
         fs::create_dir_all(&word_dir).await?;
@@ -48,7 +96,7 @@ impl WordReleaseRepository {
         Ok(())
     }
 
-    pub async fn save(&self, user_login: &str, cards: &[WordCard]) -> anyhow::Result<()> {
+    async fn save(&self, user_login: &str, cards: &[WordCard]) -> anyhow::Result<()> {
         for card in cards {
             let word_json = serde_json::to_string_pretty(card)?;
 
@@ -62,7 +110,7 @@ impl WordReleaseRepository {
         Ok(())
     }
 
-    pub async fn load_word(&self, user_login: &str, id: &str) -> anyhow::Result<WordCard> {
+    async fn load_word(&self, user_login: &str, id: &str) -> anyhow::Result<WordCard> {
         let file_path = self
             .get_word_user_path(user_login)
             .join(format!("{id}.json"));
@@ -73,7 +121,7 @@ impl WordReleaseRepository {
         Err(anyhow!("Card not found"))
     }
 
-    pub async fn list_word_ids(&self, user_login: &str) -> anyhow::Result<Vec<String>> {
+    async fn list_word_ids(&self, user_login: &str) -> anyhow::Result<Vec<String>> {
         let mut ids = Vec::new();
         let state_dir = self.get_word_user_path(user_login);
 
@@ -91,30 +139,165 @@ impl WordReleaseRepository {
         Ok(ids)
     }
 
-    pub async fn load_word_by_ids(
-        &self,
-        user_login: &str,
-        ids: &[String],
-    ) -> anyhow::Result<Vec<WordCard>> {
-        let mut cards = Vec::new();
-        for id in ids {
-            if let Ok(card) = self.load_word(user_login, id).await {
-                cards.push(card);
-            }
-        }
-        Ok(cards)
-    }
-
-    pub async fn list_all_words(&self, user_login: &str) -> anyhow::Result<Vec<WordCard>> {
+    async fn list_all_words(&self, user_login: &str) -> anyhow::Result<Vec<WordCard>> {
         let ids = self.list_word_ids(user_login).await?;
         let mut all_cards = Vec::new();
 
         for id in ids {
-            if let Ok(card) = self.load_word(user_login, &id).await {
-                all_cards.push(card);
-            }
+            all_cards.push(self.load_word(user_login, &id).await?);
         }
 
         Ok(all_cards)
     }
 }
+
+#[derive(Clone)]
+pub struct SqliteWordReleaseRepository {
+    storage: SqliteStorage,
+}
+
+impl SqliteWordReleaseRepository {
+    pub async fn new(storage: SqliteStorage) -> anyhow::Result<Self> {
+        storage
+            .call(|conn| {
+                conn.execute_batch(
+                    "CREATE TABLE IF NOT EXISTS release_words (
+                        user_login TEXT NOT NULL,
+                        id TEXT NOT NULL,
+                        data TEXT NOT NULL,
+                        PRIMARY KEY (user_login, id)
+                    );",
+                )?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(Self { storage })
+    }
+}
+
+#[async_trait]
+impl WordReleaseRepository for SqliteWordReleaseRepository {
+    async fn remove_word(&self, user_login: &str, card_id: &str) -> anyhow::Result<()> {
+        let user_login = user_login.to_owned();
+        let card_id = card_id.to_owned();
+        self.storage
+            .call(move |conn| {
+                let removed = conn.execute(
+                    "DELETE FROM release_words WHERE user_login = ?1 AND id = ?2",
+                    params![user_login, card_id],
+                )?;
+                if removed == 0 {
+                    return Err(anyhow!("Card not found"));
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn remove_word_by_ids(&self, user_login: &str, ids: &[String]) -> anyhow::Result<()> {
+        let user_login = user_login.to_owned();
+        let ids = ids.to_vec();
+        self.storage
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                for id in ids {
+                    let removed = tx.execute(
+                        "DELETE FROM release_words WHERE user_login = ?1 AND id = ?2",
+                        params![user_login, id],
+                    )?;
+                    if removed == 0 {
+                        return Err(anyhow!("Card not found"));
+                    }
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn update_word(&self, user_login: &str, card: &WordCard) -> anyhow::Result<()> {
+        self.save(user_login, std::slice::from_ref(card)).await
+    }
+
+    async fn save(&self, user_login: &str, cards: &[WordCard]) -> anyhow::Result<()> {
+        let user_login = user_login.to_owned();
+        let rows = cards
+            .iter()
+            .map(|card| Ok((card.id().to_owned(), serde_json::to_string(card)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        self.storage
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                {
+                    let mut statement = tx.prepare(
+                        "INSERT INTO release_words (user_login, id, data) VALUES (?1, ?2, ?3)
+                         ON CONFLICT (user_login, id) DO UPDATE SET data = excluded.data",
+                    )?;
+                    for (id, json) in rows {
+                        statement.execute(params![user_login, id, json])?;
+                    }
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn load_word(&self, user_login: &str, id: &str) -> anyhow::Result<WordCard> {
+        let user_login = user_login.to_owned();
+        let id = id.to_owned();
+        let json = self
+            .storage
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT data FROM release_words WHERE user_login = ?1 AND id = ?2",
+                        params![user_login, id],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .optional()?)
+            })
+            .await?;
+
+        match json {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Err(anyhow!("Card not found")),
+        }
+    }
+
+    async fn list_word_ids(&self, user_login: &str) -> anyhow::Result<Vec<String>> {
+        let user_login = user_login.to_owned();
+        self.storage
+            .call(move |conn| {
+                let mut statement =
+                    conn.prepare("SELECT id FROM release_words WHERE user_login = ?1")?;
+                let ids = statement
+                    .query_map(params![user_login], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(ids)
+            })
+            .await
+    }
+
+    async fn list_all_words(&self, user_login: &str) -> anyhow::Result<Vec<WordCard>> {
+        let user_login = user_login.to_owned();
+        let rows = self
+            .storage
+            .call(move |conn| {
+                let mut statement =
+                    conn.prepare("SELECT data FROM release_words WHERE user_login = ?1")?;
+                let rows = statement
+                    .query_map(params![user_login], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<_, _>>()?)
+    }
+}